@@ -0,0 +1,31 @@
+// Times scale9x over every sample sprite in the readme folder.
+// Run with: cargo run --release --example benchmark [iterations]
+
+use scalefx_rs::{ScaleFxOptions, png_io};
+use std::time::Instant;
+
+fn main() {
+    let iterations: usize = std::env::args().nth(1).and_then(|arg| arg.parse().ok()).unwrap_or(10);
+    let mut paths: Vec<_> = std::fs::read_dir("readme").expect("Run this from the repository root")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.to_str().is_some_and(|path| path.ends_with(".png") && !path.ends_with(".big.png")))
+        .collect();
+    paths.sort();
+    let images: Vec<_> = paths.iter().map(|path| png_io::load_png(path.to_str().unwrap()).unwrap()).collect();
+    let source_pixels: usize = images.iter().map(|image| image.pixels.len()).sum();
+
+    // Take the best time for each sprite, as that's the least disturbed by whatever else the machine is doing.
+    let options = ScaleFxOptions::default();
+    let mut best_total = 0.;
+    for image in &images {
+        let mut best = f64::MAX;
+        for _ in 0..iterations {
+            let start = Instant::now();
+            std::hint::black_box(image.scale9x(&options).unwrap());
+            best = best.min(start.elapsed().as_secs_f64() * 1000.);
+        }
+        best_total += best;
+    }
+    println!("{} sprites, {} source pixels, best of {} iterations each", images.len(), source_pixels, iterations);
+    println!("Total: {:.1}ms", best_total);
+}
@@ -0,0 +1,44 @@
+//! Animations: a sequence of whole frames with their timings, as loaded from animated GIFs and PNGs.
+
+use crate::error::ScaleFxError;
+use crate::scalefx::{Image, ScaleFxOptions};
+
+/// One frame of an animation, already composited onto the full canvas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnimationFrame {
+    pub image: Image,
+    /// How long the frame shows for, in seconds, is delay_numerator / delay_denominator.
+    /// GIFs store delays in hundredths of a second, so their denominator is 100; APNGs keep their own fractions.
+    pub delay_numerator: u16,
+    pub delay_denominator: u16,
+}
+
+/// An animation, whose frames are all the same size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Animation {
+    pub frames: Vec<AnimationFrame>,
+    /// How many times the animation plays; 0 loops forever.
+    pub plays: u32,
+}
+impl Animation {
+    /// Returns this animation with every frame scaled to 3x, keeping the timings.
+    pub fn scale3x(&self, options: &ScaleFxOptions) -> Result<Animation, ScaleFxError> {
+        self.map_frames(|image| image.scale3x(options))
+    }
+
+    /// Returns this animation with every frame scaled to 9x, keeping the timings.
+    pub fn scale9x(&self, options: &ScaleFxOptions) -> Result<Animation, ScaleFxError> {
+        self.map_frames(|image| image.scale9x(options))
+    }
+
+    /// Returns this animation with every frame scaled by any whole number factor, keeping the timings.
+    pub fn scale(&self, factor: usize, options: &ScaleFxOptions) -> Result<Animation, ScaleFxError> {
+        self.map_frames(|image| image.scale(factor, options))
+    }
+
+    /// Returns a copy with every frame's image replaced, keeping the timings.
+    pub fn map_frames(&self, mut map: impl FnMut(&Image) -> Result<Image, ScaleFxError>) -> Result<Animation, ScaleFxError> {
+        let frames = self.frames.iter().map(|frame| Ok(AnimationFrame { image: map(&frame.image)?, ..*frame })).collect::<Result<_, ScaleFxError>>()?;
+        Ok(Animation { frames, plays: self.plays })
+    }
+}
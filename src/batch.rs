@@ -0,0 +1,210 @@
+// Batch mode: scales many PNGs and GIFs into an output directory, one by one or as a group.
+
+use crate::cli::Args;
+use scalefx_rs::{Animation, AnimationFrame, Image, TagMap, scale_group};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub processed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+// Scales every input into the output directory, carrying on past failures.
+pub fn run(inputs: &[String], out_dir: &str, args: &Args) -> Summary {
+    let mut summary = Summary::default();
+    for input in inputs {
+        let jobs = match jobs_for_input(input, Path::new(out_dir)) {
+            Ok(jobs) => jobs,
+            Err(error) => {
+                eprintln!("Failed: {}: {}", input, error);
+                summary.failed += 1;
+                continue;
+            },
+        };
+        for (in_path, out_path) in jobs {
+            if !args.force && is_up_to_date(&in_path, &out_path) {
+                if !args.quiet { println!("Skipped: {}", in_path.display()) }
+                summary.skipped += 1;
+                continue;
+            }
+            match process(&in_path, &out_path, args) {
+                Ok(()) => {
+                    if !args.quiet { println!("Scaled: {} -> {}", in_path.display(), out_path.display()) }
+                    summary.processed += 1;
+                },
+                Err(error) => {
+                    eprintln!("Failed: {}: {}", in_path.display(), error);
+                    summary.failed += 1;
+                },
+            }
+        }
+    }
+    summary
+}
+
+// Scales every input together, with one shared shape; see scale_group.
+// The group is all or nothing, so if any input can't be found or scaled, none are.
+pub fn run_group(inputs: &[String], out_dir: &str, args: &Args) -> Summary {
+    let mut summary = Summary::default();
+    let mut jobs = Vec::new();
+    for input in inputs {
+        match jobs_for_input(input, Path::new(out_dir)) {
+            Ok(input_jobs) => jobs.extend(input_jobs),
+            Err(error) => {
+                eprintln!("Failed: {}: {}", input, error);
+                summary.failed += 1;
+            },
+        }
+    }
+    if summary.failed > 0 { return summary }
+    // Each output depends on every input, so they're only up to date if they all are.
+    if !args.force && jobs.iter().all(|(in_path, out_path)| is_up_to_date(in_path, out_path)) {
+        if !args.quiet { println!("Skipped: all {} inputs", jobs.len()) }
+        summary.skipped = jobs.len();
+        return summary;
+    }
+    match process_group(&jobs, args) {
+        Ok(()) => {
+            if !args.quiet {
+                for (in_path, out_path) in &jobs { println!("Scaled: {} -> {}", in_path.display(), out_path.display()) }
+            }
+            summary.processed = jobs.len();
+        },
+        Err(error) => {
+            eprintln!("Failed: group: {}", error);
+            summary.failed = jobs.len();
+        },
+    }
+    summary
+}
+
+fn process_group(jobs: &[(PathBuf, PathBuf)], args: &Args) -> Result<(), String> {
+    let mut loaded = Vec::new();
+    for (in_path, _) in jobs {
+        let input = in_path.to_str().ok_or("Path is not valid UTF-8")?;
+        let (animation, palette) = crate::load(input).map_err(|error| format!("{}: {}", in_path.display(), error))?;
+        if animation.frames.len() > 1 { return Err(format!("{}: groups only work with single images, not animations", in_path.display())) }
+        loaded.push((animation, palette));
+    }
+    let images: Vec<Image> = loaded.iter().map(|(animation, _)| animation.frames[0].image.clone()).collect();
+    if let Some(path) = &args.save_tags {
+        TagMap::for_group(&images, args.scale, &args.options).and_then(|tags| tags.save(path)).map_err(|error| error.to_string())?;
+    }
+    let scaled = scale_group(&images, args.scale, &args.options).map_err(|error| error.to_string())?;
+    for (((_, out_path), (animation, palette)), image) in jobs.iter().zip(loaded).zip(scaled) {
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        let output = out_path.to_str().ok_or("Path is not valid UTF-8")?;
+        let animation = Animation { frames: vec![AnimationFrame { image, ..animation.frames[0] }], plays: animation.plays };
+        crate::save(&animation, palette.as_ref(), output).map_err(|error| error.to_string())?;
+    }
+    Ok(())
+}
+
+fn process(in_path: &Path, out_path: &Path, args: &Args) -> Result<(), String> {
+    if let Some(parent) = out_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+    }
+    let input = in_path.to_str().ok_or("Path is not valid UTF-8")?;
+    let output = out_path.to_str().ok_or("Path is not valid UTF-8")?;
+    crate::upscale(input, output, args, false).map_err(|error| error.to_string())
+}
+
+// Expands a file, directory or glob into (input, output) pairs.
+// Directories are scanned recursively, with their structure mirrored under out_dir.
+fn jobs_for_input(input: &str, out_dir: &Path) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+    let path = Path::new(input);
+    if path.is_dir() {
+        let mut files = Vec::new();
+        find_images(path, &mut files).map_err(|error| error.to_string())?;
+        files.sort();
+        return Ok(files.into_iter().map(|file| {
+            let relative = file.strip_prefix(path).unwrap_or(&file).to_path_buf();
+            (file, out_dir.join(relative))
+        }).collect());
+    }
+    let files = if is_glob(input) { expand_glob(path)? } else if path.is_file() { vec![path.to_path_buf()] } else { return Err("No such file or directory".to_string()) };
+    Ok(files.into_iter().filter_map(|file| {
+        let name = file.file_name()?.to_owned();
+        Some((file, out_dir.join(name)))
+    }).collect())
+}
+
+fn find_images(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_images(&path, files)?;
+        } else if is_image(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("png") || extension.eq_ignore_ascii_case("gif"))
+}
+
+// An output is up to date if it was modified after its input.
+fn is_up_to_date(in_path: &Path, out_path: &Path) -> bool {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|metadata| metadata.modified());
+    match (modified(in_path), modified(out_path)) {
+        (Ok(input), Ok(output)) => output > input,
+        _ => false,
+    }
+}
+
+fn is_glob(input: &str) -> bool {
+    input.contains(['*', '?'])
+}
+
+// Expands a glob whose wildcards are in the file name, eg 'sprites/*.png'.
+fn expand_glob(pattern: &Path) -> Result<Vec<PathBuf>, String> {
+    let file_pattern = pattern.file_name().and_then(|name| name.to_str()).ok_or("Invalid glob")?;
+    let dir = match pattern.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if is_glob(&dir.to_string_lossy()) { return Err("Wildcards are only supported in the file name".to_string()) }
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir).map_err(|error| error.to_string())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.file_name().and_then(|name| name.to_str()).is_some_and(|name| wildcard_match(file_pattern.as_bytes(), name.as_bytes())))
+        .collect();
+    if files.is_empty() { return Err("No files match".to_string()) }
+    files.sort();
+    Ok(files)
+}
+
+// Matches '*' (any run of characters) and '?' (any single character).
+// On a mismatch, the last '*' takes one more character and matching resumes after it; earlier stars never need
+// revisiting, so this doesn't blow up on patterns with many stars.
+fn wildcard_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // The position after the last '*', and the text it's matched up to.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p += 1;
+                star = Some((p, t));
+            },
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            },
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    star = Some((star_p, t));
+                },
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
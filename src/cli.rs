@@ -0,0 +1,228 @@
+// Command line argument parsing for the scalefx binary.
+
+use scalefx_rs::{AlphaMode, BorderMode, ColourMetric, ScaleFxOptions, SheetLayout};
+
+pub const USAGE: &str = "Usage:
+scalefx [options] in.png out.png
+scalefx [options] --out-dir <dir> <inputs...>
+
+Images may be PNGs, animated PNGs or animated GIFs. Every frame of an animation
+is scaled, and the timings and loop count are kept whether it's saved as a .gif
+or .png.
+
+Inputs in batch mode may be image files, directories (scanned recursively,
+with the structure mirrored in the output directory), or globs such as
+'sprites/*.png'. Outputs newer than their inputs are skipped.
+
+Options:
+  -o, --out-dir <dir>   Batch mode: write every scaled input into this directory.
+  -f, --force           Batch mode: don't skip outputs that are newer than their inputs.
+  --group               Batch mode: scale every input together with one shared shape, worked out
+                        from all of them, so palette variants of a sprite match exactly. The
+                        inputs must be single images of the same size.
+  --scale <n>           Scale factor (default: 9). 3, 9 and 27 are ScaleFX's own; any other
+                        factor is shrunk from the next of those up, eg 4 and 6 from 9.
+  --threshold <0.01-1>  Colour distance threshold for corners (default: 0.5).
+  --metric <metric>     How colour differences are measured: redmean (default), shader,
+                        euclidean, ycbcr, lab76, lab2000 or oklab.
+  --alpha <mode>        How alpha counts towards colour differences: binary (default), where
+                        pixels are transparent or opaque, or premultiplied, which suits soft
+                        shadows and glows.
+  --alpha-cutoff <n>    Alphas below this (1-255) count as fully transparent (default: 128).
+                        Lower it with --alpha premultiplied, so faint pixels aren't cut off.
+  --hybrid              Follow ScaleFX with reverse anti-aliasing, as per the scalefx-hybrid
+                        preset, to sharpen art that's already anti-aliased. This blends new
+                        colours, so palettised outputs may be saved as RGBA.
+  --no-aa-filter        Don't form corners across anti-aliasing.
+  --no-corner-filter    Don't filter single corners.
+  --border <mode>       How to treat the image's edges: transparent (default), clamp,
+                        mirror or wrap. Clamp suits screenshots; wrap makes tileable
+                        textures scale to tileable textures.
+  --cell <w>x<h>        Treat the input as a sprite sheet of cells this size, scaling each
+                        cell on its own so neighbouring frames don't bleed into each other.
+  --cell-spacing <n>    Pixels between the sheet's cells (default: 0).
+  --cell-margin <n>     Pixels before the sheet's first row and column of cells (default: 0).
+  --threads <n>         Threads to split each pass across; 0 uses every core (default: 0).
+  --band-rows <n>       Process huge images in bands of this many rows to bound memory use.
+  --no-simd             Don't use SIMD instructions (the output is identical either way).
+  --dump-passes <dir>   Also write colour-coded images of each of ScaleFX's passes, for the first
+                        3x scale of each frame, into this directory, to see why it scaled as it did.
+                        Batch mode mirrors the output directory's structure in it.
+  --save-tags <file>    Also save the shape of the scale (which neighbour every subpixel is copied
+                        from) to this file, for --tags. Single images or --group only.
+  --tags <file>         Scale with the shape saved by --save-tags, instead of working one out, so
+                        palette variants of a sprite get exactly the same shape. Only for images
+                        the same size as the original, scaled with the same --scale.
+  --explain <x>,<y>     Also print every pass's values for the source pixel at x,y (from 0,0 at the
+                        top left) in each frame's first 3x scale, to see why it scaled as it did.
+  -q, --quiet           Only print errors.
+  -h, --help            Print this help.
+  -V, --version         Print the version.";
+
+// What the user asked the binary to do.
+#[derive(Debug)]
+pub enum Command {
+    Help,
+    Version,
+    Upscale(Box<Args>),
+}
+
+#[derive(Debug)]
+pub struct Args {
+    pub inputs: Vec<String>,
+    pub output: Output,
+    pub force: bool,
+    pub group: bool, // Batch mode scales every input with one shared set of tags.
+    pub scale: usize,
+    pub options: ScaleFxOptions,
+    pub sheet: Option<SheetLayout>, // Sprite sheet mode.
+    pub dump_passes: Option<String>, // Directory to write debug images of the passes into.
+    pub explain: Option<(usize, usize)>, // Source pixel to print the passes' values for.
+    pub save_tags: Option<String>, // File to save the tag map to.
+    pub tags: Option<String>, // Tag map file to scale with.
+    pub quiet: bool,
+}
+
+#[derive(Debug)]
+pub enum Output {
+    File(String), // A single input is scaled to this path.
+    Dir(String), // Batch mode: every input is scaled into this directory.
+}
+
+// Parses the arguments, excluding the program name.
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let mut options = ScaleFxOptions::default().with_threads(0);
+    let mut scale = 9;
+    let mut quiet = false;
+    let mut force = false;
+    let mut group = false;
+    let mut out_dir: Option<String> = None;
+    let mut dump_passes: Option<String> = None;
+    let mut explain: Option<(usize, usize)> = None;
+    let mut save_tags: Option<String> = None;
+    let mut tags: Option<String> = None;
+    let mut cell: Option<(usize, usize)> = None;
+    let mut cell_spacing: Option<usize> = None;
+    let mut cell_margin: Option<usize> = None;
+    let mut paths: Vec<String> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-q" | "--quiet" => quiet = true,
+            "-f" | "--force" => force = true,
+            "--group" => group = true,
+            "-o" | "--out-dir" => out_dir = Some(value(&mut args, arg)?.clone()),
+            "--dump-passes" => dump_passes = Some(value(&mut args, arg)?.clone()),
+            "--save-tags" => save_tags = Some(value(&mut args, arg)?.clone()),
+            "--tags" => tags = Some(value(&mut args, arg)?.clone()),
+            "--no-aa-filter" => options.is_filter_aa_enabled = false,
+            "--no-corner-filter" => options.filter_corners = false,
+            "--no-simd" => options.simd = false,
+            "--hybrid" => options.hybrid = true,
+            "--scale" => {
+                let factor = value(&mut args, arg)?;
+                scale = factor.parse().ok().filter(|&factor| factor > 0).ok_or_else(|| format!("--scale must be a whole number from 1 up, but was {}", factor))?;
+            },
+            "--border" => {
+                options.border_mode = match value(&mut args, arg)?.as_str() {
+                    "transparent" => BorderMode::Transparent,
+                    "clamp" => BorderMode::Clamp,
+                    "mirror" => BorderMode::Mirror,
+                    "wrap" => BorderMode::Wrap,
+                    other => return Err(format!("--border must be transparent, clamp, mirror or wrap, but was {}", other)),
+                };
+            },
+            "--metric" => {
+                options.colour_metric = match value(&mut args, arg)?.as_str() {
+                    "redmean" => ColourMetric::Redmean,
+                    "shader" => ColourMetric::Shader,
+                    "euclidean" => ColourMetric::Euclidean,
+                    "ycbcr" => ColourMetric::YCbCr,
+                    "lab76" => ColourMetric::Lab76,
+                    "lab2000" => ColourMetric::Lab2000,
+                    "oklab" => ColourMetric::Oklab,
+                    other => return Err(format!("--metric must be redmean, shader, euclidean, ycbcr, lab76, lab2000 or oklab, but was {}", other)),
+                };
+            },
+            "--alpha" => {
+                options.alpha_mode = match value(&mut args, arg)?.as_str() {
+                    "binary" => AlphaMode::Binary,
+                    "premultiplied" => AlphaMode::Premultiplied,
+                    other => return Err(format!("--alpha must be binary or premultiplied, but was {}", other)),
+                };
+            },
+            "--alpha-cutoff" => {
+                let cutoff = value(&mut args, arg)?;
+                options.alpha_cutoff = cutoff.parse().map_err(|_| format!("--alpha-cutoff must be a whole number from 1 to 255, but was {}", cutoff))?;
+                options.validate().map_err(|error| error.to_string())?;
+            },
+            "--explain" => {
+                let pixel = value(&mut args, arg)?;
+                let parsed = pixel.split_once(',').and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)));
+                explain = Some(parsed.ok_or_else(|| format!("--explain must be a pixel like 12,34, but was {}", pixel))?);
+            },
+            "--cell" => {
+                let size = value(&mut args, arg)?;
+                let parsed = size.split_once('x').and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+                cell = Some(parsed.ok_or_else(|| format!("--cell must be a size like 16x24, but was {}", size))?);
+            },
+            "--cell-spacing" => {
+                let spacing = value(&mut args, arg)?;
+                cell_spacing = Some(spacing.parse().map_err(|_| format!("--cell-spacing must be a whole number, but was {}", spacing))?);
+            },
+            "--cell-margin" => {
+                let margin = value(&mut args, arg)?;
+                cell_margin = Some(margin.parse().map_err(|_| format!("--cell-margin must be a whole number, but was {}", margin))?);
+            },
+            "--threads" => {
+                let threads = value(&mut args, arg)?;
+                options.threads = threads.parse().map_err(|_| format!("--threads must be a whole number, but was {}", threads))?;
+            },
+            "--band-rows" => {
+                let band_rows = value(&mut args, arg)?;
+                options.band_rows = band_rows.parse().map_err(|_| format!("--band-rows must be a whole number, but was {}", band_rows))?;
+            },
+            "--threshold" => {
+                let threshold = value(&mut args, arg)?;
+                options.threshold = threshold.parse().map_err(|_| format!("--threshold must be a number, but was {}", threshold))?;
+                options.validate().map_err(|error| error.to_string())?;
+            },
+            other if other.starts_with('-') && other.len() > 1 => return Err(format!("Unknown option: {}", other)),
+            path => paths.push(path.to_string()),
+        }
+    }
+    let sheet = match cell {
+        Some((width, height)) => {
+            let layout = SheetLayout::new(width, height).with_spacing(cell_spacing.unwrap_or(0)).with_margin(cell_margin.unwrap_or(0));
+            layout.validate().map_err(|error| error.to_string())?;
+            Some(layout)
+        },
+        None if cell_spacing.is_some() || cell_margin.is_some() => return Err("--cell-spacing and --cell-margin need --cell".to_string()),
+        None => None,
+    };
+    if sheet.is_some() && (save_tags.is_some() || tags.is_some()) { return Err("--save-tags and --tags don't work with --cell".to_string()) }
+    if out_dir.is_some() && !group && save_tags.is_some() { return Err("--save-tags only works with a single input or --group".to_string()) }
+    if group {
+        if out_dir.is_none() { return Err("--group needs --out-dir".to_string()) }
+        if sheet.is_some() { return Err("--group doesn't work with --cell".to_string()) }
+        if tags.is_some() { return Err("--group and --tags both pick the shape, so only use one".to_string()) }
+    }
+    let (inputs, output) = match out_dir {
+        Some(dir) => {
+            if paths.is_empty() { return Err("Expected at least one input".to_string()) }
+            (paths, Output::Dir(dir))
+        },
+        None => {
+            let [input, output]: [String; 2] = paths.try_into().map_err(|paths: Vec<String>| format!("Expected an input and an output path, but got {}", paths.len()))?;
+            (vec![input], Output::File(output))
+        },
+    };
+    Ok(Command::Upscale(Box::new(Args { inputs, output, force, group, scale, options, sheet, dump_passes, explain, save_tags, tags, quiet })))
+}
+
+// Gets the value following an option.
+fn value<'a>(args: &mut impl Iterator<Item = &'a String>, option: &str) -> Result<&'a String, String> {
+    args.next().ok_or_else(|| format!("{} needs a value", option))
+}
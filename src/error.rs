@@ -0,0 +1,103 @@
+//! Errors that can occur while scaling, loading or saving images.
+
+use std::fmt;
+
+/// Everything that can go wrong in ScaleFX-rs.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ScaleFxError {
+    /// The pixel count doesn't match width x height.
+    DimensionMismatch { width: usize, height: usize, pixels: usize },
+    /// The image has no pixels; ScaleFX needs at least one.
+    EmptyImage,
+    /// The scaled image would be too large to address in memory.
+    OutputTooLarge { width: usize, height: usize },
+    /// A palette index is past the end of its palette.
+    IndexOutOfRange { index: u8, palette_len: usize },
+    /// The options are outside the ranges the shader allows.
+    InvalidOptions(String),
+    /// The pixel asked about is outside the image.
+    PixelOutOfBounds { x: usize, y: usize, width: usize, height: usize },
+    /// A tag map was applied to an image or factor other than the ones it was made for.
+    TagMapMismatch(String),
+    /// A group of images to scale together had none in it.
+    EmptyGroup,
+    /// A group of images to scale together weren't all the same size.
+    GroupSizeMismatch { width: usize, height: usize, other_width: usize, other_height: usize },
+    /// The file couldn't be opened, created or written.
+    Io(std::io::Error),
+    /// The input couldn't be decoded.
+    Decode(String),
+    /// The output couldn't be encoded.
+    Encode(String),
+}
+
+impl fmt::Display for ScaleFxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DimensionMismatch { width, height, pixels } => write!(f, "Expected {} x {} = {} pixels, but got {}", width, height, width.saturating_mul(*height), pixels),
+            Self::EmptyImage => write!(f, "Image is empty"),
+            Self::OutputTooLarge { width, height } => write!(f, "Scaling a {} x {} image would overflow the output size", width, height),
+            Self::IndexOutOfRange { index, palette_len } => write!(f, "Palette index {} is out of range for a palette of {} colours", index, palette_len),
+            Self::InvalidOptions(reason) => write!(f, "Invalid options: {}", reason),
+            Self::PixelOutOfBounds { x, y, width, height } => write!(f, "Pixel {},{} is outside the {} x {} image", x, y, width, height),
+            Self::TagMapMismatch(reason) => write!(f, "Tag map mismatch: {}", reason),
+            Self::EmptyGroup => write!(f, "A group needs at least one image"),
+            Self::GroupSizeMismatch { width, height, other_width, other_height } => write!(f, "Every image in a group must be the same size, but there's {} x {} and {} x {}", width, height, other_width, other_height),
+            Self::Io(error) => write!(f, "I/O error: {}", error),
+            Self::Decode(reason) => write!(f, "Failed to decode: {}", reason),
+            Self::Encode(reason) => write!(f, "Failed to encode: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ScaleFxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ScaleFxError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<png::DecodingError> for ScaleFxError {
+    fn from(error: png::DecodingError) -> Self {
+        match error {
+            png::DecodingError::IoError(error) => Self::Io(error),
+            error => Self::Decode(error.to_string()),
+        }
+    }
+}
+
+impl From<png::EncodingError> for ScaleFxError {
+    fn from(error: png::EncodingError) -> Self {
+        match error {
+            png::EncodingError::IoError(error) => Self::Io(error),
+            error => Self::Encode(error.to_string()),
+        }
+    }
+}
+
+impl From<gif::DecodingError> for ScaleFxError {
+    fn from(error: gif::DecodingError) -> Self {
+        match error {
+            gif::DecodingError::Io(error) => Self::Io(error),
+            error => Self::Decode(error.to_string()),
+        }
+    }
+}
+
+impl From<gif::EncodingError> for ScaleFxError {
+    fn from(error: gif::EncodingError) -> Self {
+        match error {
+            gif::EncodingError::Io(error) => Self::Io(error),
+            error => Self::Encode(error.to_string()),
+        }
+    }
+}
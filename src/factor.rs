@@ -0,0 +1,96 @@
+//! Scaling by any whole number factor, not just 3x and 9x.
+//!
+//! ScaleFX itself only ever triples, so the powers of 3 (3x, 9x, 27x, ...) are native: ScaleFX simply runs that many
+//! times. Every other factor is derived from the next native factor up (2x from 3x; 4x to 8x from 9x; 10x to 26x
+//! from 27x), by shrinking it so each output pixel takes whichever colour covers most of its area. That keeps ScaleFX's
+//! crisp edges and never introduces new colours, unlike a smooth resample, at the cost of scaling further than asked.
+//! 1x is the image as is.
+
+use crate::error::ScaleFxError;
+use crate::scalefx::{self, ScaleFxOptions};
+
+/// Returns the native factor an image is scaled to on the way to the given factor: the smallest power of 3 that's
+/// at least as big. For native factors, that's the factor itself. Returns None if that power of 3 is too big for a
+/// usize, which is only the case for factors above 3^40 on 64 bit platforms.
+pub fn native_factor(factor: usize) -> Option<usize> {
+    let mut native: usize = 1;
+    while native < factor { native = native.checked_mul(3)? }
+    Some(native)
+}
+
+/// Returns whether ScaleFX produces the given factor directly, rather than it being shrunk from a bigger one.
+pub fn is_native(factor: usize) -> bool {
+    factor > 0 && native_factor(factor) == Some(factor)
+}
+
+/// Scales by any whole number factor using ScaleFX.
+/// Pixels are in 0xRRGGBBAA format.
+/// Returns width, height, pixels.
+pub fn scale(width: usize, height: usize, pixels: &[u32], factor: usize) -> (usize, usize, Vec<u32>) {
+    scale_with_options(width, height, pixels, factor, &ScaleFxOptions::default())
+}
+
+/// As per `scale`, but with custom options.
+/// Panics if the factor, options or dimensions are invalid; see `try_scale` for the fallible version.
+pub fn scale_with_options(width: usize, height: usize, pixels: &[u32], factor: usize, options: &ScaleFxOptions) -> (usize, usize, Vec<u32>) {
+    try_scale(width, height, pixels, factor, options).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Scales by any whole number factor, returning an error instead of panicking if the factor, options or dimensions
+/// are invalid. See the module docs for which factors are native, and how the rest are derived.
+pub fn try_scale(width: usize, height: usize, pixels: &[u32], factor: usize, options: &ScaleFxOptions) -> Result<(usize, usize, Vec<u32>), ScaleFxError> {
+    let native = validate_factor(factor)?;
+    options.validate()?;
+    scalefx::validate_dimensions(width, height, pixels.len(), native)?; // Check up front so the first pass doesn't waste its time.
+    let mut scaled = (width, height, pixels.to_vec());
+    for _ in 0..native.ilog(3) {
+        scaled = scalefx::try_scale3x(scaled.0, scaled.1, &scaled.2, options)?;
+    }
+    Ok(shrink(scaled.0, scaled.1, &scaled.2, native, factor))
+}
+
+// Scale factors start at 1, and need a native factor that fits in a usize. Returns the native factor.
+pub(crate) fn validate_factor(factor: usize) -> Result<usize, ScaleFxError> {
+    if factor == 0 { return Err(ScaleFxError::InvalidOptions("scale factor must be at least 1".to_string())) }
+    native_factor(factor).ok_or_else(|| ScaleFxError::InvalidOptions(format!("scale factor {} is too large", factor)))
+}
+
+// Shrinks an image that was scaled by `from` to as if it were scaled by `to` instead, where `to` is at most `from`.
+// Each output pixel takes the value covering the most of its area, with ties going to the first in reading order.
+// Works on colours and palette indices alike, since it only ever copies values.
+pub(crate) fn shrink<T: Copy + PartialEq>(width: usize, height: usize, pixels: &[T], from: usize, to: usize) -> (usize, usize, Vec<T>) {
+    if from == to { return (width, height, pixels.to_vec()) }
+    let out_width = width / from * to;
+    let out_height = height / from * to;
+    let columns = coverage(out_width, from, to);
+    let rows = coverage(out_height, from, to);
+    let mut out = Vec::with_capacity(out_width * out_height);
+    let mut votes: Vec<(T, usize)> = Vec::new();
+    for row in &rows {
+        for column in &columns {
+            votes.clear();
+            for &(y, row_overlap) in row {
+                for &(x, column_overlap) in column {
+                    let value = pixels[y * width + x];
+                    let area = row_overlap * column_overlap;
+                    match votes.iter_mut().find(|(voted, _)| *voted == value) {
+                        Some(vote) => vote.1 += area,
+                        None => votes.push((value, area)),
+                    }
+                }
+            }
+            let winner = votes.iter().fold(votes[0], |best, &vote| if vote.1 > best.1 { vote } else { best });
+            out.push(winner.0);
+        }
+    }
+    (out_width, out_height, out)
+}
+
+// For each output pixel along one axis, the input pixels it overlaps and by how much.
+// Measured in units where an input pixel is `to` long and an output pixel is `from` long, so overlaps are whole numbers.
+fn coverage(out_len: usize, from: usize, to: usize) -> Vec<Vec<(usize, usize)>> {
+    (0..out_len).map(|out| {
+        let (start, end) = (out * from, (out + 1) * from);
+        (start / to..end.div_ceil(to)).map(|input| (input, end.min((input + 1) * to) - start.max(input * to))).collect()
+    }).collect()
+}
@@ -0,0 +1,158 @@
+//! Loading and saving animated GIFs as `Animation`s.
+
+use crate::animation::{Animation, AnimationFrame};
+use crate::error::ScaleFxError;
+use crate::scalefx::Image;
+use gif::{ColorOutput, DisposalMethod, Repeat};
+use std::collections::HashMap;
+
+/// Loads a GIF, compositing each frame onto the full canvas as per its disposal method.
+pub fn load_gif(path: &str) -> Result<Animation, ScaleFxError> {
+    let in_file = std::fs::File::open(path)?;
+    let in_reader = std::io::BufReader::new(in_file);
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(ColorOutput::RGBA);
+    let mut decoder = options.read_info(in_reader)?;
+    let width = decoder.width() as usize;
+    let height = decoder.height() as usize;
+    let plays = match decoder.repeat() {
+        Repeat::Infinite => 0,
+        Repeat::Finite(repeats) => repeats as u32 + 1, // Without a loop count, it plays once.
+    };
+
+    let mut canvas = vec![0u32; width * height];
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame()? {
+        // Frames may sit partly outside the canvas, so only the overlap is drawn.
+        let left = frame.left as usize;
+        let top = frame.top as usize;
+        let columns = left.min(width)..(left + frame.width as usize).min(width);
+        let rows = top.min(height)..(top + frame.height as usize).min(height);
+        let previous = if frame.dispose == DisposalMethod::Previous { Some(canvas.clone()) } else { None };
+        for y in rows.clone() {
+            for x in columns.clone() {
+                let i = ((y - top) * frame.width as usize + x - left) * 4;
+                let pixel = u32::from_be_bytes([frame.buffer[i], frame.buffer[i + 1], frame.buffer[i + 2], frame.buffer[i + 3]]);
+                if pixel & 0xff != 0 { canvas[y * width + x] = pixel } // Transparent pixels let the canvas show through.
+            }
+        }
+        frames.push(AnimationFrame {
+            image: Image::new(width, height, canvas.clone()),
+            delay_numerator: frame.delay,
+            delay_denominator: 100,
+        });
+        match (frame.dispose, previous) {
+            (DisposalMethod::Background, _) => { // Browsers clear to transparent rather than the background colour.
+                for y in rows.clone() { canvas[y * width + columns.start..y * width + columns.end].fill(0) }
+            },
+            (DisposalMethod::Previous, Some(previous)) => canvas = previous,
+            _ => {},
+        }
+    }
+    if frames.is_empty() {
+        return Err(ScaleFxError::Decode("GIF has no frames".to_string()));
+    }
+    Ok(Animation { frames, plays })
+}
+
+/// Saves an animation as a GIF.
+/// GIFs only have on/off transparency, so pixels with an alpha under 0x80 become transparent and the rest opaque.
+/// The palette is rebuilt from the frames' colours; ScaleFX never introduces new colours (unless its hybrid mode
+/// blended some), so a scaled GIF fits in the same number of colours.
+/// If the animation as a whole has too many colours, each frame gets its own palette.
+pub fn save_gif(animation: &Animation, path: &str) -> Result<(), ScaleFxError> {
+    let first = &animation.frames.first().ok_or_else(|| ScaleFxError::Encode("Animation has no frames".to_string()))?.image;
+    let too_large = || ScaleFxError::OutputTooLarge { width: first.width, height: first.height };
+    let width = u16::try_from(first.width).map_err(|_| too_large())?;
+    let height = u16::try_from(first.height).map_err(|_| too_large())?;
+    for frame in &animation.frames {
+        let image = &frame.image;
+        if (image.width, image.height) != (first.width, first.height) {
+            return Err(ScaleFxError::Encode("Every frame of a GIF must be the same size".to_string()));
+        }
+        if image.pixels.len() != image.width.saturating_mul(image.height) {
+            return Err(ScaleFxError::DimensionMismatch { width: image.width, height: image.height, pixels: image.pixels.len() });
+        }
+    }
+
+    let global_palette = Palette::new(animation.frames.iter().map(|frame| &frame.image));
+    let file = std::fs::File::create(path)?;
+    let buf_writer = std::io::BufWriter::new(file);
+    let mut encoder = gif::Encoder::new(buf_writer, width, height, global_palette.as_ref().map_or(&[], |palette| &palette.rgb))?;
+    match animation.plays {
+        0 => encoder.set_repeat(Repeat::Infinite)?,
+        1 => {}, // Playing once is the default.
+        plays => encoder.set_repeat(Repeat::Finite(u16::try_from(plays - 1).unwrap_or(u16::MAX)))?,
+    }
+    for (index, frame) in animation.frames.iter().enumerate() {
+        let local_palette = match global_palette {
+            Some(_) => None,
+            None => Some(Palette::new(std::iter::once(&frame.image))
+                .ok_or_else(|| ScaleFxError::Encode(format!("Frame {} has more than the 256 colours a GIF allows", index + 1)))?),
+        };
+        let palette = global_palette.as_ref().or(local_palette.as_ref()).unwrap();
+        let indices: Vec<u8> = frame.image.pixels.iter().map(|&pixel| palette.index_of(pixel)).collect();
+        encoder.write_frame(&gif::Frame {
+            delay: gif_delay(frame.delay_numerator, frame.delay_denominator),
+            // Each frame is a whole image, so the canvas is cleared before the next one is drawn.
+            dispose: DisposalMethod::Background,
+            transparent: palette.transparent,
+            width,
+            height,
+            palette: local_palette.map(|palette| palette.rgb),
+            buffer: indices.into(),
+            ..gif::Frame::default()
+        })?;
+    }
+    encoder.into_inner()?;
+    Ok(())
+}
+
+// Converts a delay in seconds to the nearest hundredth.
+fn gif_delay(numerator: u16, denominator: u16) -> u16 {
+    let denominator = if denominator == 0 { 100 } else { denominator as u32 }; // APNG treats 0 as hundredths.
+    ((numerator as u32 * 100 + denominator / 2) / denominator).min(u16::MAX as u32) as u16
+}
+
+// The colours used by some images, in the order they're first seen, plus a transparent entry at the end if needed.
+struct Palette {
+    rgb: Vec<u8>, // Red, green, blue for each entry.
+    indices: HashMap<u32, u8>, // 0xRRGGBB00 to the entry's index.
+    transparent: Option<u8>,
+}
+impl Palette {
+    // Returns None if there are more than the 256 colours a GIF allows.
+    fn new<'a>(images: impl Iterator<Item = &'a Image>) -> Option<Self> {
+        let mut rgb = Vec::new();
+        let mut indices = HashMap::new();
+        let mut has_transparency = false;
+        for &pixel in images.flat_map(|image| &image.pixels) {
+            if is_transparent(pixel) {
+                has_transparency = true;
+                continue;
+            }
+            let colour = pixel & 0xffffff00;
+            if indices.contains_key(&colour) { continue }
+            indices.insert(colour, u8::try_from(indices.len()).ok()?);
+            rgb.extend_from_slice(&colour.to_be_bytes()[..3]);
+        }
+        let transparent = if has_transparency {
+            let index = u8::try_from(indices.len()).ok()?;
+            rgb.extend_from_slice(&[0, 0, 0]);
+            Some(index)
+        } else {
+            None
+        };
+        Some(Self { rgb, indices, transparent })
+    }
+
+    fn index_of(&self, pixel: u32) -> u8 {
+        if is_transparent(pixel) { self.transparent.unwrap_or(0) } else { self.indices[&(pixel & 0xffffff00)] }
+    }
+}
+
+// GIFs can't show anything between transparent and opaque, so half alpha is the fairest split. That's the same as
+// the default alpha_cutoff, but saving doesn't know which options the image was scaled with, so it's fixed.
+fn is_transparent(pixel: u32) -> bool {
+    pixel & 0xff < 0x80
+}
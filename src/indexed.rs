@@ -0,0 +1,96 @@
+//! Scaling palette indices rather than colours, so palette cycling and swaps still work after scaling.
+//!
+//! ScaleFX only ever copies source pixels, so each scaled pixel can be an index too.
+//! The distances between colours are worked out once per pair of palette entries, rather than once per pixel.
+
+use crate::error::ScaleFxError;
+use crate::factor;
+use crate::scalefx::{self, DistanceTable, Image, ScaleFxOptions};
+
+/// An image of palette indices, stored row by row, along with the 0xRRGGBBAA palette they index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedImage {
+    pub width: usize,
+    pub height: usize,
+    pub indices: Vec<u8>,
+    pub palette: Vec<u32>,
+}
+impl IndexedImage {
+    pub fn new(width: usize, height: usize, indices: Vec<u8>, palette: Vec<u32>) -> Self {
+        Self { width, height, indices, palette }
+    }
+
+    /// Returns this image scaled to 3x, with the same palette.
+    pub fn scale3x(&self, options: &ScaleFxOptions) -> Result<IndexedImage, ScaleFxError> {
+        let (width, height, indices) = try_scale3x_indexed(self.width, self.height, &self.indices, &self.palette, options)?;
+        Ok(IndexedImage { width, height, indices, palette: self.palette.clone() })
+    }
+
+    /// Returns this image scaled to 9x, with the same palette.
+    pub fn scale9x(&self, options: &ScaleFxOptions) -> Result<IndexedImage, ScaleFxError> {
+        let (width, height, indices) = try_scale9x_indexed(self.width, self.height, &self.indices, &self.palette, options)?;
+        Ok(IndexedImage { width, height, indices, palette: self.palette.clone() })
+    }
+
+    /// Returns this image scaled by any whole number factor, with the same palette.
+    pub fn scale(&self, factor: usize, options: &ScaleFxOptions) -> Result<IndexedImage, ScaleFxError> {
+        let (width, height, indices) = try_scale_indexed(self.width, self.height, &self.indices, &self.palette, factor, options)?;
+        Ok(IndexedImage { width, height, indices, palette: self.palette.clone() })
+    }
+
+    /// Returns the image in colour, by looking up each index in the palette.
+    /// Indices past the end of the palette become transparent.
+    pub fn to_image(&self) -> Image {
+        Image::new(self.width, self.height, self.indices.iter().map(|&index| self.palette.get(index as usize).copied().unwrap_or(0)).collect())
+    }
+}
+
+/// Scales palette indices to 3x using ScaleFX, judging edges by the distances between the palette's 0xRRGGBBAA colours,
+/// as measured by the options' colour metric and alpha mode.
+/// With `BorderMode::Transparent`, the transparency smoothed in around the edges uses the palette's first transparent
+/// entry (alpha under the options' cutoff), so it's an error if there isn't one and the transparency shows.
+/// Returns width, height, indices.
+pub fn try_scale3x_indexed(width: usize, height: usize, indices: &[u8], palette: &[u32], options: &ScaleFxOptions) -> Result<(usize, usize, Vec<u8>), ScaleFxError> {
+    scale_indexed(width, height, indices, palette, options, 1)
+}
+
+/// As per `try_scale3x_indexed`, but to 9x by running ScaleFX twice.
+pub fn try_scale9x_indexed(width: usize, height: usize, indices: &[u8], palette: &[u32], options: &ScaleFxOptions) -> Result<(usize, usize, Vec<u8>), ScaleFxError> {
+    scalefx::validate_dimensions(width, height, indices.len(), 9)?; // Check up front so the first pass doesn't waste its time.
+    scale_indexed(width, height, indices, palette, options, 2)
+}
+
+/// As per `try_scale3x_indexed`, but by any whole number factor. Factors that aren't powers of 3 are shrunk from the
+/// next power of 3 up, as per `factor`, which picks between indices just as it would between colours.
+pub fn try_scale_indexed(width: usize, height: usize, indices: &[u8], palette: &[u32], factor: usize, options: &ScaleFxOptions) -> Result<(usize, usize, Vec<u8>), ScaleFxError> {
+    let native = factor::validate_factor(factor)?;
+    options.validate()?;
+    scalefx::validate_dimensions(width, height, indices.len(), native)?;
+    let (width, height, indices) = scale_indexed(width, height, indices, palette, options, native.ilog(3) as usize)?;
+    Ok(factor::shrink(width, height, &indices, native, factor))
+}
+
+// Runs the given number of 3x scales, sharing the distance table between them.
+fn scale_indexed(width: usize, height: usize, indices: &[u8], palette: &[u32], options: &ScaleFxOptions, times: usize) -> Result<(usize, usize, Vec<u8>), ScaleFxError> {
+    if options.hybrid {
+        return Err(ScaleFxError::InvalidOptions("hybrid mode blends new colours, so it can't scale palette indices".to_string()));
+    }
+    if let Some(&index) = indices.iter().find(|&&index| index as usize >= palette.len()) {
+        return Err(ScaleFxError::IndexOutOfRange { index, palette_len: palette.len() });
+    }
+    let table = DistanceTable::new(palette, options);
+    let mut scaled = (width, height, indices.iter().map(|&index| index as u32 + 1).collect::<Vec<u32>>());
+    for _ in 0..times {
+        scaled = scalefx::try_scale3x_with_distances(scaled.0, scaled.1, &scaled.2, Some(&table), options)?;
+    }
+
+    // Back from the table's indices, where 0 is the transparent border, to palette indices.
+    let transparent = palette.iter().take(256).position(|colour| colour & 0xff < options.alpha_cutoff as u32);
+    let (width, height, pixels) = scaled;
+    let indices = pixels.into_iter().map(|pixel| match (pixel, transparent) {
+        (0, Some(transparent)) => Ok(transparent as u8),
+        (0, None) => Err(ScaleFxError::InvalidOptions("the palette has no transparent entry for the transparent border, so use another border mode".to_string())),
+        (pixel, _) => Ok((pixel - 1) as u8),
+    }).collect::<Result<_, _>>()?;
+    Ok((width, height, indices))
+}
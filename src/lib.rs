@@ -0,0 +1,25 @@
+//! ScaleFX pixel art upscaler, on the CPU (not a shader!), in Rust.
+//!
+//! ```
+//! let (width, height, pixels) = (2, 1, vec![0xff0000ff, 0x0000ffff]);
+//! let (width, height, pixels) = scalefx_rs::scale3x(width, height, &pixels);
+//! assert_eq!((width, height, pixels.len()), (6, 3, 18));
+//! ```
+//!
+//! Original shader algorithm thanks to Sp00kyFox, 2016.
+
+pub mod animation;
+pub mod error;
+pub mod factor;
+pub mod gif_io;
+pub mod indexed;
+pub mod png_io;
+pub mod scalefx;
+pub mod sheet;
+
+pub use animation::{Animation, AnimationFrame};
+pub use error::ScaleFxError;
+pub use factor::{scale, scale_with_options, try_scale};
+pub use indexed::{IndexedImage, try_scale3x_indexed, try_scale9x_indexed, try_scale_indexed};
+pub use scalefx::{AlphaMode, BorderMode, ColourMetric, Explanation, Image, PassDump, ScaleFxOptions, TagMap, dump_passes, explain, scale_group, scale3x, scale3x_with_options, scale9x, scale9x_with_options, try_scale3x, try_scale9x};
+pub use sheet::{SheetLayout, scale3x_sheet, scale9x_sheet, scale_sheet};
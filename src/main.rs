@@ -1,6 +1,9 @@
-mod scalefx;
+mod batch;
+mod cli;
 
-use png::{self, BitDepth, ColorType, Transformations};
+use scalefx_rs::png_io::PngPalette;
+use scalefx_rs::{Animation, Image, ScaleFxError, TagMap, gif_io, png_io, sheet};
+use std::path::{Path, PathBuf};
 
 // https://patorjk.com/software/taag/#p=display&f=Bloody&t=ScaleFX&x=none
 const LOGO: &str = "
@@ -17,70 +20,149 @@ const LOGO: &str = "
 ";
 
 fn main() {
-    println!("{}", LOGO.trim());
-    println!("-=[ ScaleFX-rs Pixel Art Upscaler ]=-");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = match cli::parse(&args) {
+        Ok(cli::Command::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        },
+        Ok(cli::Command::Version) => {
+            println!("scalefx {}", env!("CARGO_PKG_VERSION"));
+            return;
+        },
+        Ok(cli::Command::Upscale(args)) => args,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            eprintln!("{}", cli::USAGE);
+            std::process::exit(2);
+        },
+    };
 
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() < 3 {
-        println!("Usage:");
-        println!("scalefx in.png out.png");
-    } else {
-        upscale(&args[1], &args[2]);
+    if !args.quiet {
+        println!("{}", LOGO.trim());
+        println!("-=[ ScaleFX-rs Pixel Art Upscaler ]=-");
+    }
+    match &args.output {
+        cli::Output::File(output) => {
+            if let Err(error) = upscale(&args.inputs[0], output, &args, !args.quiet) {
+                eprintln!("Error: {}", error);
+                std::process::exit(1);
+            }
+        },
+        cli::Output::Dir(out_dir) => {
+            let summary = if args.group { batch::run_group(&args.inputs, out_dir, &args) } else { batch::run(&args.inputs, out_dir, &args) };
+            if !args.quiet {
+                println!("Processed: {}, skipped: {}, failed: {}", summary.processed, summary.skipped, summary.failed);
+            }
+            if summary.failed > 0 {
+                std::process::exit(1);
+            }
+        },
     }
+}
 
-    // let (width, height, pixels) = sample();
-    // std::fs::write("sample.png", png::png_data(width, height, &pixels)).unwrap();
+// Loads, scales and saves a single image or animation.
+// Animated GIFs and PNGs have every frame scaled; the output's extension picks whether it's saved as a GIF or PNG.
+fn upscale(input: &str, output: &str, args: &cli::Args, verbose: bool) -> Result<(), ScaleFxError> {
+    let log = |message: String| if verbose { println!("{}", message) };
+    log(format!("Loading: {}", input));
+    let (animation, palette) = load(input)?;
+    let first = &animation.frames[0].image;
+    log(format!("Loaded: {} x {} px, {} frame(s)", first.width, first.height, animation.frames.len()));
+    if let Some(dir) = &args.dump_passes {
+        log(format!("Dumping passes to: {}", dir));
+        dump_passes(input, output, &animation, dir, args)?;
+    }
+    if let Some((x, y)) = args.explain {
+        explain(&animation, x, y, args)?;
+    }
+    if let Some(path) = &args.save_tags {
+        log(format!("Saving tags: {}", path));
+        single_frame(&animation)?;
+        TagMap::new(first, args.scale, &args.options)?.save(path)?;
+    }
+    let tags = match &args.tags {
+        Some(path) => {
+            log(format!("Loading tags: {}", path));
+            single_frame(&animation)?;
+            Some(TagMap::load(path)?)
+        },
+        None => None,
+    };
+    log("Scaling...".to_string());
+    let animation = animation.map_frames(|image| scale(image, args, tags.as_ref()))?;
+    let first = &animation.frames[0].image;
+    log(format!("Scaled to: {} x {}", first.width, first.height));
+    log(format!("Saving: {}", output));
+    save(&animation, palette.as_ref(), output)
+}
 
-    // let (width, height, pixels) = scalefx::scale3x(width, height, &pixels);
-    // std::fs::write("out.big.png", png::png_data(width, height, &pixels)).unwrap();
+// Loads an image or animation, along with its palette if it's a palettised PNG.
+fn load(input: &str) -> Result<(Animation, Option<PngPalette>), ScaleFxError> {
+    let animation = if is_gif(input) { gif_io::load_gif(input)? } else { png_io::load_apng(input)? };
+    let palette = if is_gif(input) { None } else { png_io::load_png_palette(input)? };
+    Ok((animation, palette))
+}
 
-    // let (width, height, pixels) = scalefx::scale3x(width, height, &pixels);
-    // std::fs::write("out.bigger.png", png::png_data(width, height, &pixels)).unwrap();
+// Saves a scaled image or animation; the output's extension picks whether it's saved as a GIF or PNG.
+fn save(animation: &Animation, palette: Option<&PngPalette>, output: &str) -> Result<(), ScaleFxError> {
+    if is_gif(output) {
+        gif_io::save_gif(animation, output)
+    } else if animation.frames.len() == 1 {
+        // Palettised inputs stay palettised, with the same palette.
+        match palette {
+            Some(palette) => png_io::save_png_with_palette(&animation.frames[0].image, palette, output),
+            None => png_io::save_png(&animation.frames[0].image, output),
+        }
+    } else {
+        png_io::save_apng(animation, output)
+    }
 }
 
-fn upscale(in_path: &str, out: &str) {
-    let (width, height, pixels) = load_png(in_path);
-    println!("Scaling...");
-    let (width, height, pixels) = scalefx::scale9x(width, height, &pixels);
-    println!("Scaled to: {} x {}", width, height);
-    save_png(width, height, &pixels, out);
+// Scales a single image up to the requested scale, with the tag map's shape if there is one.
+fn scale(image: &Image, args: &cli::Args, tags: Option<&TagMap>) -> Result<Image, ScaleFxError> {
+    match (&args.sheet, tags) {
+        (_, Some(tags)) => tags.apply(image, args.scale, &args.options),
+        (Some(layout), None) => sheet::scale_sheet(image, layout, args.scale, &args.options),
+        (None, None) => image.scale(args.scale, &args.options),
+    }
 }
 
-fn load_png(path: &str) -> (usize, usize, Vec<u32>) {
-    println!("Loading: {}", path);
-    let in_file = std::fs::File::open(path).expect("Failed to open file!");
-    let in_reader = std::io::BufReader::new(in_file);
-    let mut decoder = png::Decoder::new(in_reader);
-    decoder.set_transformations(Transformations::ALPHA | Transformations::STRIP_16); // Auto-converts to 8-bit RGBA.
-    let mut reader = decoder.read_info().expect("Failed to read header");
-    let mut buf = vec![0; reader.output_buffer_size().unwrap()];
-    let info = reader.next_frame(&mut buf).expect("Failed to decode");
-    let bytes = &buf[..info.buffer_size()];
-    assert_eq!(info.bit_depth, BitDepth::Eight);
-    assert_eq!(info.color_type, ColorType::Rgba);
-    // Convert to RGBA
-    let mut rgbas: Vec<u32> = Vec::with_capacity(info.width as usize * info.height as usize);
-    for chunk in bytes.chunks_exact(4) {
-        rgbas.push(u32::from_be_bytes(chunk.try_into().unwrap()));
+// Tag maps are the shape of one image, so can't be shared by an animation's frames.
+fn single_frame(animation: &Animation) -> Result<(), ScaleFxError> {
+    if animation.frames.len() > 1 { return Err(ScaleFxError::InvalidOptions("tag maps only work with single images, not animations".to_string())) }
+    Ok(())
+}
+
+// Saves colour-coded images of each pass of every frame's first 3x scale, named after the input.
+// In batch mode they mirror the output's path under the output directory instead, so inputs with the same name in
+// different directories don't overwrite each other's.
+fn dump_passes(input: &str, output: &str, animation: &Animation, dir: &str, args: &cli::Args) -> Result<(), ScaleFxError> {
+    let stem = match &args.output {
+        cli::Output::Dir(out_dir) => Path::new(output).strip_prefix(out_dir).unwrap_or(Path::new(output)).with_extension(""),
+        cli::Output::File(_) => PathBuf::from(Path::new(input).file_stem().unwrap_or("image".as_ref())),
+    };
+    let stem = Path::new(dir).join(stem);
+    std::fs::create_dir_all(stem.parent().unwrap_or(Path::new(dir)))?;
+    for (index, frame) in animation.frames.iter().enumerate() {
+        let dump = scalefx_rs::dump_passes(&frame.image, &args.options)?;
+        let prefix = if animation.frames.len() == 1 { stem.display().to_string() } else { format!("{}.{}", stem.display(), index + 1) };
+        for (name, image) in dump.images() {
+            png_io::save_png(image, &format!("{}.{}.png", prefix, name))?;
+        }
     }
-    println!("Loaded: {} x {} px", info.width, info.height);
-    (info.width as usize, info.height as usize, rgbas)
+    Ok(())
 }
 
-fn save_png(width: usize, height: usize, pixels: &[u32], path: &str) {
-    println!("Saving: {}", path);
-    let file = std::fs::File::create(path).unwrap();
-    let ref mut buf_writer = std::io::BufWriter::new(file);
-    let mut encoder = png::Encoder::new(buf_writer, width as u32, height as u32);
-    encoder.set_color(png::ColorType::Rgba);
-    encoder.set_depth(png::BitDepth::Eight);
-    let mut writer = encoder.write_header().unwrap();
-    let mut data: Vec<u8> = Vec::with_capacity(width * height * 4);
-    for p in pixels {
-        data.push((p >> 24) as u8);
-        data.push((p >> 16) as u8);
-        data.push((p >> 8) as u8);
-        data.push(*p as u8);
+// Prints every pass's values for one pixel of every frame's first 3x scale.
+fn explain(animation: &Animation, x: usize, y: usize, args: &cli::Args) -> Result<(), ScaleFxError> {
+    for (index, frame) in animation.frames.iter().enumerate() {
+        if animation.frames.len() > 1 { println!("Frame {}:", index + 1) }
+        print!("{}", scalefx_rs::explain(&frame.image, x, y, &args.options)?);
     }
-    writer.write_image_data(&data).unwrap(); // Save
+    Ok(())
+}
+
+fn is_gif(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gif"))
 }
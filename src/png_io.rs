@@ -0,0 +1,255 @@
+//! Loading and saving PNGs as `Image`s, and animated PNGs (APNGs) as `Animation`s.
+
+use crate::animation::{Animation, AnimationFrame};
+use crate::error::ScaleFxError;
+use crate::scalefx::Image;
+use png::{BitDepth, BlendOp, ColorType, DisposeOp, Transformations};
+use std::collections::HashMap;
+
+/// Loads a PNG, converting it to 8-bit RGBA.
+/// For an animated PNG, this is the default image that viewers without APNG support show.
+pub fn load_png(path: &str) -> Result<Image, ScaleFxError> {
+    let mut reader = open(path)?;
+    let mut buf = frame_buffer(&reader)?;
+    let info = reader.next_frame(&mut buf)?;
+    to_rgbas(&info, &buf).map(|rgbas| Image::new(info.width as usize, info.height as usize, rgbas))
+}
+
+/// Loads every frame of an animated PNG, compositing each onto the full canvas as per its blend and dispose ops.
+/// A PNG that isn't animated loads as a single frame that plays once.
+pub fn load_apng(path: &str) -> Result<Animation, ScaleFxError> {
+    let mut reader = open(path)?;
+    let mut buf = frame_buffer(&reader)?;
+    let width = reader.info().width as usize;
+    let height = reader.info().height as usize;
+    let Some(control) = reader.info().animation_control().copied() else {
+        let image = Image::new(width, height, to_rgbas(&reader.next_frame(&mut buf)?, &buf)?);
+        return Ok(Animation { frames: vec![AnimationFrame { image, delay_numerator: 0, delay_denominator: 100 }], plays: 1 });
+    };
+    if reader.info().frame_control().is_none() {
+        reader.next_frame(&mut buf)?; // Without a fcTL, the default image isn't part of the animation.
+    }
+
+    let mut canvas = vec![0u32; width * height];
+    let mut frames = Vec::new();
+    for _ in 0..control.num_frames {
+        let info = reader.next_frame(&mut buf)?;
+        let pixels = to_rgbas(&info, &buf)?;
+        let frame_control = *reader.info().frame_control().ok_or_else(|| ScaleFxError::Decode("APNG frame has no fcTL".to_string()))?;
+        // Frames are meant to sit within the canvas, but only the overlap is drawn in case they don't.
+        let left = frame_control.x_offset as usize;
+        let top = frame_control.y_offset as usize;
+        let columns = left.min(width)..(left + info.width as usize).min(width);
+        let rows = top.min(height)..(top + info.height as usize).min(height);
+        // Disposing of the first frame to the previous state is treated as clearing it.
+        let dispose = if frame_control.dispose_op == DisposeOp::Previous && frames.is_empty() { DisposeOp::Background } else { frame_control.dispose_op };
+        let previous = if dispose == DisposeOp::Previous { Some(canvas.clone()) } else { None };
+        for y in rows.clone() {
+            for x in columns.clone() {
+                let pixel = pixels[(y - top) * info.width as usize + x - left];
+                let destination = &mut canvas[y * width + x];
+                *destination = match frame_control.blend_op {
+                    BlendOp::Source => pixel,
+                    BlendOp::Over => blend_over(pixel, *destination),
+                };
+            }
+        }
+        frames.push(AnimationFrame {
+            image: Image::new(width, height, canvas.clone()),
+            delay_numerator: frame_control.delay_num,
+            delay_denominator: frame_control.delay_den,
+        });
+        match (dispose, previous) {
+            (DisposeOp::Background, _) => {
+                for y in rows.clone() { canvas[y * width + columns.start..y * width + columns.end].fill(0) }
+            },
+            (DisposeOp::Previous, Some(previous)) => canvas = previous,
+            _ => {},
+        }
+    }
+    if frames.is_empty() {
+        return Err(ScaleFxError::Decode("APNG has no frames".to_string()));
+    }
+    Ok(Animation { frames, plays: control.num_plays })
+}
+
+/// The palette of an indexed-colour PNG, as stored in its PLTE and tRNS chunks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PngPalette {
+    /// Red, green, blue for each entry.
+    pub rgb: Vec<u8>,
+    /// The alpha of each entry. It may be shorter than the palette, in which case the rest are opaque, and is empty
+    /// if there's no tRNS chunk.
+    pub alphas: Vec<u8>,
+}
+impl PngPalette {
+    /// Each entry as 0xRRGGBBAA, which is how `load_png` loads it.
+    pub fn colours(&self) -> Vec<u32> {
+        self.rgb.chunks_exact(3).enumerate()
+            .map(|(i, rgb)| u32::from_be_bytes([rgb[0], rgb[1], rgb[2], self.alphas.get(i).copied().unwrap_or(0xff)]))
+            .collect()
+    }
+
+    // Finds each pixel's entry, the first if there are duplicates, or None if some pixel isn't in the palette.
+    fn indices_of(&self, image: &Image) -> Option<Vec<u8>> {
+        let colours = self.colours();
+        let mut indices = HashMap::new();
+        for (index, &colour) in colours.iter().enumerate().rev() {
+            indices.insert(colour, u8::try_from(index).ok()?);
+        }
+        let transparent = colours.iter().position(|colour| colour & 0xff == 0).map(|index| index as u8);
+        image.pixels.iter()
+            .map(|pixel| indices.get(pixel).copied().or(if pixel & 0xff == 0 { transparent } else { None }))
+            .collect()
+    }
+}
+
+/// Loads the palette of an indexed-colour PNG, or None if it isn't one.
+pub fn load_png_palette(path: &str) -> Result<Option<PngPalette>, ScaleFxError> {
+    let reader = open(path)?;
+    let info = reader.info();
+    if info.color_type != ColorType::Indexed {
+        return Ok(None);
+    }
+    Ok(info.palette.as_ref().map(|rgb| PngPalette {
+        rgb: rgb.to_vec(),
+        alphas: info.trns.as_ref().map_or_else(Vec::new, |alphas| alphas.to_vec()),
+    }))
+}
+
+fn open(path: &str) -> Result<png::Reader<std::io::BufReader<std::fs::File>>, ScaleFxError> {
+    let in_file = std::fs::File::open(path)?;
+    let in_reader = std::io::BufReader::new(in_file);
+    let mut decoder = png::Decoder::new(in_reader);
+    decoder.set_transformations(Transformations::ALPHA | Transformations::STRIP_16); // Auto-converts to 8-bit RGBA (or grey + alpha).
+    Ok(decoder.read_info()?)
+}
+
+// A buffer big enough for any frame, as APNG frames can't be larger than the image.
+fn frame_buffer<R: std::io::BufRead + std::io::Seek>(reader: &png::Reader<R>) -> Result<Vec<u8>, ScaleFxError> {
+    let size = reader.output_buffer_size().ok_or_else(|| ScaleFxError::Decode("Image is too large".to_string()))?;
+    Ok(vec![0; size])
+}
+
+// Converts a decoded frame to RGBA.
+fn to_rgbas(info: &png::OutputInfo, buf: &[u8]) -> Result<Vec<u32>, ScaleFxError> {
+    let bytes = &buf[..info.buffer_size()];
+    if info.bit_depth != BitDepth::Eight {
+        return Err(ScaleFxError::Decode(format!("Unsupported bit depth: {:?}", info.bit_depth)));
+    }
+    match info.color_type {
+        ColorType::Rgba => Ok(bytes.chunks_exact(4).map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]])).collect()),
+        ColorType::GrayscaleAlpha => Ok(bytes.chunks_exact(2).map(|c| u32::from_be_bytes([c[0], c[0], c[0], c[1]])).collect()),
+        other => Err(ScaleFxError::Decode(format!("Unsupported colour type: {:?}", other))),
+    }
+}
+
+// Alpha-composites a non-premultiplied RGBA pixel over another, rounding to the nearest value.
+fn blend_over(source: u32, destination: u32) -> u32 {
+    let source_alpha = source & 0xff;
+    if source_alpha == 0xff { return source }
+    if source_alpha == 0 { return destination }
+    let destination_weight = (destination & 0xff) * (0xff - source_alpha); // The destination's alpha, scaled by 0xff.
+    let alpha = source_alpha * 0xff + destination_weight; // Also scaled by 0xff.
+    let channel = |shift: u32| {
+        let mix = ((source >> shift) & 0xff) * source_alpha * 0xff + ((destination >> shift) & 0xff) * destination_weight;
+        (mix + alpha / 2) / alpha
+    };
+    (channel(24) << 24) | (channel(16) << 16) | (channel(8) << 8) | ((alpha + 0x7f) / 0xff)
+}
+
+/// Saves an image as an 8-bit RGBA PNG.
+pub fn save_png(image: &Image, path: &str) -> Result<(), ScaleFxError> {
+    let mut encoder = create_encoder(image, path)?;
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    let mut data: Vec<u8> = Vec::with_capacity(image.pixels.len() * 4);
+    for p in &image.pixels {
+        data.extend_from_slice(&p.to_be_bytes());
+    }
+    writer.write_image_data(&data)?; // Save
+    writer.finish()?;
+    Ok(())
+}
+
+/// Saves an image as an indexed-colour PNG with the given palette and tRNS chunk, unchanged.
+/// ScaleFX only copies source pixels, so a scaled image fits the palette it was loaded with. The one exception is
+/// the transparency added around the edges by `BorderMode::Transparent`, so fully transparent pixels use the palette's
+/// first fully transparent entry. If some colour still isn't in the palette, this falls back to `save_png`.
+pub fn save_png_with_palette(image: &Image, palette: &PngPalette, path: &str) -> Result<(), ScaleFxError> {
+    let Some(indices) = palette.indices_of(image) else { return save_png(image, path) };
+    let mut encoder = create_encoder(image, path)?;
+    let entries = palette.rgb.len() / 3;
+    let bits = [1, 2, 4, 8].into_iter().find(|&bits| entries <= 1 << bits).unwrap_or(8); // The smallest depth that fits.
+    encoder.set_color(ColorType::Indexed);
+    encoder.set_depth(BitDepth::from_u8(bits as u8).unwrap());
+    encoder.set_palette(palette.rgb.as_slice());
+    if !palette.alphas.is_empty() {
+        encoder.set_trns(palette.alphas.as_slice());
+    }
+    let mut writer = encoder.write_header()?;
+    // Pack the indices into bytes, high bits first, with each row starting on a new byte.
+    let per_byte = 8 / bits;
+    let mut data: Vec<u8> = Vec::with_capacity(image.height * image.width.div_ceil(per_byte));
+    for row in indices.chunks_exact(image.width) {
+        for chunk in row.chunks(per_byte) {
+            data.push(chunk.iter().enumerate().fold(0, |byte, (i, &index)| byte | index << (8 - bits * (i + 1))));
+        }
+    }
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(())
+}
+
+// Checks the image's dimensions and starts a PNG of its size.
+fn create_encoder(image: &Image, path: &str) -> Result<png::Encoder<'static, std::io::BufWriter<std::fs::File>>, ScaleFxError> {
+    if image.pixels.len() != image.width.saturating_mul(image.height) {
+        return Err(ScaleFxError::DimensionMismatch { width: image.width, height: image.height, pixels: image.pixels.len() });
+    }
+    let width = u32::try_from(image.width).map_err(|_| ScaleFxError::OutputTooLarge { width: image.width, height: image.height })?;
+    let height = u32::try_from(image.height).map_err(|_| ScaleFxError::OutputTooLarge { width: image.width, height: image.height })?;
+    let file = std::fs::File::create(path)?;
+    let buf_writer = std::io::BufWriter::new(file);
+    Ok(png::Encoder::new(buf_writer, width, height))
+}
+
+/// Saves an animation as an 8-bit RGBA animated PNG, keeping each frame's delay and the loop count.
+/// Every frame is written whole, replacing the one before.
+pub fn save_apng(animation: &Animation, path: &str) -> Result<(), ScaleFxError> {
+    let first = &animation.frames.first().ok_or_else(|| ScaleFxError::Encode("Animation has no frames".to_string()))?.image;
+    let too_large = || ScaleFxError::OutputTooLarge { width: first.width, height: first.height };
+    let width = u32::try_from(first.width).map_err(|_| too_large())?;
+    let height = u32::try_from(first.height).map_err(|_| too_large())?;
+    for frame in &animation.frames {
+        let image = &frame.image;
+        if (image.width, image.height) != (first.width, first.height) {
+            return Err(ScaleFxError::Encode("Every frame of an APNG must be the same size".to_string()));
+        }
+        if image.pixels.len() != image.width.saturating_mul(image.height) {
+            return Err(ScaleFxError::DimensionMismatch { width: image.width, height: image.height, pixels: image.pixels.len() });
+        }
+    }
+
+    let file = std::fs::File::create(path)?;
+    let buf_writer = std::io::BufWriter::new(file);
+    let mut encoder = png::Encoder::new(buf_writer, width, height);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    let num_frames = u32::try_from(animation.frames.len()).map_err(|_| ScaleFxError::Encode("Too many frames for an APNG".to_string()))?;
+    encoder.set_animated(num_frames, animation.plays)?;
+    let mut writer = encoder.write_header()?;
+    writer.set_dispose_op(DisposeOp::None)?;
+    writer.set_blend_op(BlendOp::Source)?;
+    let mut data: Vec<u8> = Vec::with_capacity(first.pixels.len() * 4);
+    for frame in &animation.frames {
+        writer.set_frame_delay(frame.delay_numerator, frame.delay_denominator)?;
+        data.clear();
+        for p in &frame.image.pixels {
+            data.extend_from_slice(&p.to_be_bytes());
+        }
+        writer.write_image_data(&data)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
@@ -1,40 +1,250 @@
-// Scales to 3x using ScaleFX.
-// Pixels are in 0xRRGGBBAA format.
-// Returns width, height, pixels.
+//! The ScaleFX algorithm, ported from the libretro shaders to run on the CPU.
+//!
+//! Pixels are `u32`s in 0xRRGGBBAA format, stored row by row.
+//! Each 3x scale runs the shader's five passes in turn, from colour distances through to subpixel output.
+
+use crate::error::ScaleFxError;
+use crate::factor;
+use std::ops::Range;
+
+mod debug;
+mod explain;
+mod hybrid;
+mod metric;
+mod simd;
+mod tag_map;
+
+pub use debug::{PassDump, dump_passes};
+pub use explain::{Explanation, explain};
+pub use metric::{AlphaMode, ColourMetric};
+pub use tag_map::{TagMap, scale_group};
+
+/// Scales to 3x using ScaleFX.
+/// Pixels are in 0xRRGGBBAA format.
+/// Returns width, height, pixels.
 pub fn scale3x(width: usize, height: usize, pixels: &[u32]) -> (usize, usize, Vec<u32>) {
-    let image = add_transparent_border(width, height, pixels);
-    let distances = calculate_distances(&image);
-    let corners = calculate_corner_strengths(&distances);
-    let configurations = resolve_corner_configurations(&corners);
-    let edges = determine_edge_levels(&configurations);
-    let big = scale_subpixels(&edges);
-    let sans_border = remove_transparent_border(&big);
-    (sans_border.width, sans_border.height, sans_border.pixels)
+    scale3x_with_options(width, height, pixels, &ScaleFxOptions::default())
 }
 
-// 9x scaling is reasonable; any higher is asking a bit much though.
+/// Scales to 9x by running ScaleFX twice.
+/// 9x scaling is reasonable; any higher is asking a bit much though.
 pub fn scale9x(width: usize, height: usize, pixels: &[u32]) -> (usize, usize, Vec<u32>) {
-    let (width, height, pixels) = scale3x(width, height, pixels);
-    scale3x(width, height, &pixels)
+    scale9x_with_options(width, height, pixels, &ScaleFxOptions::default())
 }
 
-// ScaleFX options:
-const THRESHOLD: f32 = 0.5; // Min 0.01; max: 1; step: 0.01
-const IS_FILTER_AA_ENABLED: bool = true;
-const FILTER_CORNERS: bool = true; // SFX_SCN in the shader.
+/// As per `scale3x`, but with custom options.
+/// Panics if the options or dimensions are invalid; see `try_scale3x` for the fallible version.
+pub fn scale3x_with_options(width: usize, height: usize, pixels: &[u32], options: &ScaleFxOptions) -> (usize, usize, Vec<u32>) {
+    try_scale3x(width, height, pixels, options).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// As per `scale9x`, but with custom options.
+/// Panics if the options or dimensions are invalid; see `try_scale9x` for the fallible version.
+pub fn scale9x_with_options(width: usize, height: usize, pixels: &[u32], options: &ScaleFxOptions) -> (usize, usize, Vec<u32>) {
+    try_scale9x(width, height, pixels, options).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Scales to 3x, returning an error instead of panicking if the options or dimensions are invalid.
+pub fn try_scale3x(width: usize, height: usize, pixels: &[u32], options: &ScaleFxOptions) -> Result<(usize, usize, Vec<u32>), ScaleFxError> {
+    try_scale3x_with_distances(width, height, pixels, None, options)
+}
+
+// As per `try_scale3x`, but if there's a distance table the pixels are indices into it rather than colours.
+pub(crate) fn try_scale3x_with_distances(width: usize, height: usize, pixels: &[u32], distances: Option<&DistanceTable>, options: &ScaleFxOptions) -> Result<(usize, usize, Vec<u32>), ScaleFxError> {
+    options.validate()?;
+    validate_dimensions(width, height, pixels.len(), 3)?;
+    let border = options.border_mode.border_size();
+    let image = add_border(width, height, pixels, options.border_mode);
+    let sans_border = if options.band_rows == 0 || options.band_rows + HALO * 2 >= image.height {
+        run_passes(&image, border..image.height-border, border..image.width-border, distances, options)
+    } else {
+        run_passes_in_bands(&image, border, distances, options)
+    };
+    Ok((sans_border.width, sans_border.height, sans_border.pixels))
+}
+
+/// Scales to 9x, returning an error instead of panicking if the options or dimensions are invalid.
+pub fn try_scale9x(width: usize, height: usize, pixels: &[u32], options: &ScaleFxOptions) -> Result<(usize, usize, Vec<u32>), ScaleFxError> {
+    validate_dimensions(width, height, pixels.len(), 9)?; // Check up front so the first pass doesn't waste its time.
+    let (width, height, pixels) = try_scale3x(width, height, pixels, options)?;
+    try_scale3x(width, height, &pixels, options)
+}
+
+// Checks the pixel count matches, and that the padded, scaled output fits in memory.
+pub(crate) fn validate_dimensions(width: usize, height: usize, pixel_count: usize, scale: usize) -> Result<(), ScaleFxError> {
+    if width == 0 || height == 0 { return Err(ScaleFxError::EmptyImage) }
+    if width.checked_mul(height) != Some(pixel_count) {
+        return Err(ScaleFxError::DimensionMismatch { width, height, pixels: pixel_count });
+    }
+    let too_large = || ScaleFxError::OutputTooLarge { width, height };
+    let out_width = width.checked_add(2).and_then(|w| w.checked_mul(scale)).ok_or_else(too_large)?;
+    let out_height = height.checked_add(2).and_then(|h| h.checked_mul(scale)).ok_or_else(too_large)?;
+    let out_bytes = out_width.checked_mul(out_height).and_then(|p| p.checked_mul(std::mem::size_of::<u32>())).ok_or_else(too_large)?;
+    if out_bytes > isize::MAX as usize { return Err(too_large()) }
+    Ok(())
+}
 
-// Adds a 1px transparent border so the algorithm looks nice on edges.
-fn add_transparent_border(width: usize, height: usize, pixels: &[u32]) -> Image {
-    let new_width = width + 2;
-    let new_height = height + 2;
+/// ScaleFX options, mirroring the shader's parameters.
+/// https://github.com/libretro/slang-shaders/blob/master/edge-smoothing/scalefx/shaders/scalefx-pass1.slang
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct ScaleFxOptions {
+    /// SFX_THR in the shader: colour distances above this never form corners. Min 0.01; max: 1; step: 0.01
+    pub threshold: f32,
+    /// SFX_AA in the shader: whether corners may form across anti-aliasing.
+    pub is_filter_aa_enabled: bool,
+    /// SFX_SCN in the shader: whether single corners are filtered.
+    pub filter_corners: bool,
+    /// How many threads each pass splits its rows across. 0 uses every available core; 1 runs serially.
+    /// The output is identical regardless.
+    pub threads: usize,
+    /// Process the image in horizontal bands of this many rows, so the intermediate passes
+    /// only need memory proportional to the band rather than the whole image. 0 processes
+    /// the whole image at once. The output is identical regardless.
+    pub band_rows: usize,
+    /// Use SIMD instructions for the colour distance and corner strength passes, where the CPU supports them
+    /// (currently SSE4.1 on x86_64). Only the redmean metric has a SIMD colour distance. The output is identical regardless.
+    pub simd: bool,
+    /// How the image is extended beyond its edges.
+    pub border_mode: BorderMode,
+    /// How the difference between two colours is measured.
+    pub colour_metric: ColourMetric,
+    /// How alpha counts towards the difference between two colours.
+    pub alpha_mode: AlphaMode,
+    /// Pixels with an alpha below this count as fully transparent, like the offscreen pixels around the image.
+    /// Min: 1, so that alpha 0 is always transparent.
+    pub alpha_cutoff: u8,
+    /// As per the scalefx-hybrid preset, follow the subpixel pass with reverse anti-aliasing, which sharpens the
+    /// ramps of already anti-aliased art. Only subpixels ScaleFX left as their own pixel are blended, so its
+    /// smoothed edges are kept. This blends new colours, so it can't be used when scaling palette indices.
+    pub hybrid: bool,
+}
+impl Default for ScaleFxOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            is_filter_aa_enabled: true,
+            filter_corners: true,
+            threads: 1,
+            band_rows: 0,
+            simd: true,
+            border_mode: BorderMode::Transparent,
+            colour_metric: ColourMetric::Redmean,
+            alpha_mode: AlphaMode::Binary,
+            alpha_cutoff: 0x80,
+            hybrid: false,
+        }
+    }
+}
+impl ScaleFxOptions {
+    pub const MIN_THRESHOLD: f32 = 0.01;
+    pub const MAX_THRESHOLD: f32 = 1.;
+
+    /// Returns a copy with the given threshold, clamped to the range the shader allows.
+    pub fn with_threshold(self, threshold: f32) -> Self {
+        let threshold = if threshold.is_nan() { Self::default().threshold } else { threshold.clamp(Self::MIN_THRESHOLD, Self::MAX_THRESHOLD) };
+        Self { threshold, ..self }
+    }
+
+    /// Returns a copy that runs on the given number of threads; 0 uses every available core.
+    pub fn with_threads(self, threads: usize) -> Self {
+        Self { threads, ..self }
+    }
+
+    /// Returns a copy that processes the image in bands of the given number of rows; 0 disables banding.
+    pub fn with_band_rows(self, band_rows: usize) -> Self {
+        Self { band_rows, ..self }
+    }
+
+    /// Returns a copy with SIMD enabled or disabled.
+    pub fn with_simd(self, simd: bool) -> Self {
+        Self { simd, ..self }
+    }
+
+    /// Returns a copy with the given border mode.
+    pub fn with_border_mode(self, border_mode: BorderMode) -> Self {
+        Self { border_mode, ..self }
+    }
+
+    /// Returns a copy with the given colour metric.
+    pub fn with_colour_metric(self, colour_metric: ColourMetric) -> Self {
+        Self { colour_metric, ..self }
+    }
+
+    /// Returns a copy with the given alpha mode.
+    pub fn with_alpha_mode(self, alpha_mode: AlphaMode) -> Self {
+        Self { alpha_mode, ..self }
+    }
+
+    /// Returns a copy with the given alpha cutoff, raised to the minimum of 1 if need be.
+    pub fn with_alpha_cutoff(self, alpha_cutoff: u8) -> Self {
+        Self { alpha_cutoff: alpha_cutoff.max(1), ..self }
+    }
+
+    /// Returns a copy with the hybrid reverse anti-aliasing post pass enabled or disabled.
+    pub fn with_hybrid(self, hybrid: bool) -> Self {
+        Self { hybrid, ..self }
+    }
+
+    // The number of threads to actually use.
+    pub(crate) fn thread_count(&self) -> usize {
+        match self.threads {
+            0 => std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+            threads => threads,
+        }
+    }
+
+    /// True if all the options are within the ranges the shader allows.
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    /// Returns an error describing the first option that is outside the range the shader allows.
+    pub fn validate(&self) -> Result<(), ScaleFxError> {
+        if !(Self::MIN_THRESHOLD..=Self::MAX_THRESHOLD).contains(&self.threshold) {
+            return Err(ScaleFxError::InvalidOptions(format!("threshold must be between {} and {}, but was {}", Self::MIN_THRESHOLD, Self::MAX_THRESHOLD, self.threshold)));
+        }
+        if self.alpha_cutoff == 0 {
+            return Err(ScaleFxError::InvalidOptions("alpha cutoff must be at least 1, so that alpha 0 is transparent".to_string()));
+        }
+        Ok(())
+    }
+}
+
+// Runs all five passes over a bordered image, plus the hybrid post pass if it's enabled, returning the given rows and columns at 3x.
+fn run_passes(image: &Image, rows: Range<usize>, columns: Range<usize>, distances: Option<&DistanceTable>, options: &ScaleFxOptions) -> Image {
+    let distances = calculate_distances(image, distances, options);
+    let corners = calculate_corner_strengths(&distances, options);
+    let configurations = resolve_corner_configurations(&distances, &corners, options);
+    let edges = determine_edge_levels(&configurations, options);
+    let scaled = scale_subpixels(image, &edges, rows.clone(), columns.clone(), options);
+    if options.hybrid { hybrid::reverse_anti_alias(image, &edges, &scaled, rows, columns, options) } else { scaled }
+}
+
+// How many pixels away can influence a pixel's output.
+// Pass 0 reads 1 pixel away, passes 1 and 2 read 1 either side, pass 3 reads 3, and pass 4 reads 2.
+const HALO: usize = 8;
+
+// Runs all five passes over horizontal bands of a bordered image, so the intermediate
+// passes only ever hold one band (plus its halo) at a time. Each band's halo rows are
+// thrown away, and the border is removed as the bands are stitched together.
+// The output is identical to running the passes over the whole image.
+fn run_passes_in_bands(image: &Image, border: usize, distances: Option<&DistanceTable>, options: &ScaleFxOptions) -> Image {
+    let new_width = (image.width - border * 2) * 3;
+    let new_height = (image.height - border * 2) * 3;
     let mut out: Vec<u32> = Vec::with_capacity(new_width * new_height);
-    for _ in 0..new_width { out.push(0); } // Top row.
-    for row in pixels.chunks_exact(width) {
-        out.push(0);
-        out.extend_from_slice(row);
-        out.push(0);
+    for start in (border..image.height-border).step_by(options.band_rows) { // Skip the border rows.
+        let end = (start + options.band_rows).min(image.height - border);
+        let halo_start = start.saturating_sub(HALO);
+        let halo_end = (end + HALO).min(image.height);
+        let band = Image {
+            width: image.width,
+            height: halo_end - halo_start,
+            pixels: image.pixels[halo_start * image.width..halo_end * image.width].to_vec(),
+        };
+        let big = run_passes(&band, start - halo_start..end - halo_start, border..image.width-border, distances, options);
+        out.extend_from_slice(&big.pixels);
     }
-    for _ in 0..new_width { out.push(0); } // Bottom row.
     Image {
         width: new_width,
         height: new_height,
@@ -42,13 +252,22 @@ fn add_transparent_border(width: usize, height: usize, pixels: &[u32]) -> Image
     }
 }
 
-// Removes the 3px transparent border after scaling.
-fn remove_transparent_border(image: &Image) -> Image {
-    let new_width = image.width - 6;
-    let new_height = image.height - 6;
+// Surrounds the image with a border made as per the border mode, so the passes see
+// the edge pixels' neighbours the way the mode describes.
+fn add_border(width: usize, height: usize, pixels: &[u32], mode: BorderMode) -> Image {
+    let border = mode.border_size();
+    let new_width = width + border * 2;
+    let new_height = height + border * 2;
     let mut out: Vec<u32> = Vec::with_capacity(new_width * new_height);
-    for row in image.pixels.chunks_exact(image.width).skip(3).take(new_height) {
-        out.extend_from_slice(&row[3..3+new_width]);
+    for y in 0..new_height {
+        let source_y = mode.resolve(y as isize - border as isize, height);
+        for x in 0..new_width {
+            let source_x = mode.resolve(x as isize - border as isize, width);
+            out.push(match (source_x, source_y) {
+                (Some(x), Some(y)) => pixels[y * width + x],
+                _ => 0,
+            });
+        }
     }
     Image {
         width: new_width,
@@ -57,44 +276,102 @@ fn remove_transparent_border(image: &Image) -> Image {
     }
 }
 
-#[derive(Debug)]
-struct Image {
-    width: usize,
-    height: usize,
-    pixels: Vec<u32>,
+/// How the image is extended beyond its edges, which decides how pixels along the edges are smoothed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum BorderMode {
+    /// Surrounded by transparent pixels, as per the shader. Suits sprites, whose edges are smoothed like the rest of their outline.
+    #[default]
+    Transparent,
+    /// The edge pixels extend outwards forever. Suits screenshots and opaque backgrounds.
+    Clamp,
+    /// Reflected at each edge, so the edge pixels are repeated back-to-front: `c b a | a b c`.
+    Mirror,
+    /// Wrapped around, so the left edge continues on from the right and the top from the bottom.
+    /// Every pass sees the image as if it were tiled forever, so a tileable input scales to a tileable output.
+    Wrap,
 }
+impl BorderMode {
+    // How wide a border the passes need to see to treat the edges correctly.
+    // The shader's transparent border is only 1px, and the passes treat anything past that as offscreen.
+    // The other modes need to cover everything that can influence the edge pixels.
+    fn border_size(self) -> usize {
+        match self {
+            Self::Transparent => 1,
+            Self::Clamp | Self::Mirror | Self::Wrap => HALO,
+        }
+    }
 
-// Determine the human-perceived difference between two colours.
-// For humanity's sake, r and g and b are weighted differently.
-// https://www.compuphase.com/cmetric.htm
-// Returns 0 for same colours; 1 for white-black/transparent.
-fn colour_distance(a: u32, b: u32) -> f32 {
-    let a_r = a >> 24;
-    let a_g = (a >> 16) & 0xff;
-    let a_b = (a >> 8) & 0xff;
-    let a_a = a & 0xff;
+    // Maps a row or column index, which may be outside the image, to the one it takes its pixel from.
+    // None means a transparent pixel.
+    fn resolve(self, index: isize, len: usize) -> Option<usize> {
+        let len = len as isize;
+        if (0..len).contains(&index) { return Some(index as usize) }
+        match self {
+            Self::Transparent => None,
+            Self::Clamp => Some(index.clamp(0, len - 1) as usize),
+            Self::Mirror => {
+                let index = index.rem_euclid(len * 2);
+                Some(if index < len { index } else { len * 2 - 1 - index } as usize)
+            },
+            Self::Wrap => Some(index.rem_euclid(len) as usize),
+        }
+    }
+}
+
+/// An image of 0xRRGGBBAA pixels, stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u32>,
+}
+impl Image {
+    pub fn new(width: usize, height: usize, pixels: Vec<u32>) -> Self {
+        Self { width, height, pixels }
+    }
 
-    let b_r = b >> 24;
-    let b_g = (b >> 16) & 0xff;
-    let b_b = (b >> 8) & 0xff;
-    let b_a = b & 0xff;
+    /// Returns this image scaled to 3x.
+    pub fn scale3x(&self, options: &ScaleFxOptions) -> Result<Image, ScaleFxError> {
+        let (width, height, pixels) = try_scale3x(self.width, self.height, &self.pixels, options)?;
+        Ok(Image { width, height, pixels })
+    }
 
-    if a_a < 0x80 && b_a < 0x80 { return 0. } // Transparent vs transparent counts as the same.
-    if a_a < 0x80 || b_a < 0x80 { return 1. } // Colour -> transparent counts as different.
+    /// Returns this image scaled to 9x.
+    pub fn scale9x(&self, options: &ScaleFxOptions) -> Result<Image, ScaleFxError> {
+        let (width, height, pixels) = try_scale9x(self.width, self.height, &self.pixels, options)?;
+        Ok(Image { width, height, pixels })
+    }
 
-    let r_mean = (a_r + b_r) / 2;
-    let r = a_r.abs_diff(b_r);
-    let g = a_g.abs_diff(b_g);
-    let b = a_b.abs_diff(b_b);
+    /// Returns this image scaled by any whole number factor; see `factor` for how non-powers of 3 are derived.
+    pub fn scale(&self, factor: usize, options: &ScaleFxOptions) -> Result<Image, ScaleFxError> {
+        let (width, height, pixels) = factor::try_scale(self.width, self.height, &self.pixels, factor, options)?;
+        Ok(Image { width, height, pixels })
+    }
+}
 
-    if r == 0 && g == 0 && b == 0 { return 0. } // Save the conplicated calculation below.
+// The colour distance between every pair of palette entries, for scaling palette indices instead of colours.
+// Index 0 is the transparent border, so pixels are the palette index + 1.
+pub(crate) struct DistanceTable {
+    len: usize,
+    distances: Vec<f32>, // Row by row, from each entry to every entry.
+}
+impl DistanceTable {
+    pub(crate) fn new(palette: &[u32], options: &ScaleFxOptions) -> Self {
+        // u8 indices can only reach the first 256 entries, so any more would only make the table bigger.
+        let colours: Vec<u32> = std::iter::once(0).chain(palette.iter().take(256).copied()).collect();
+        let distance = |a, b| options.colour_metric.distance_with_alpha(a, b, options.alpha_mode, options.alpha_cutoff);
+        let distances = colours.iter().flat_map(|&a| colours.iter().map(move |&b| distance(a, b))).collect();
+        Self { len: colours.len(), distances }
+    }
 
-    (((((512 + r_mean)*r*r)>>8) + 4*g*g + (((767-r_mean)*b*b)>>8)) as f32).sqrt() / 765.
+    fn distance(&self, a: u32, b: u32) -> f32 {
+        self.distances[a as usize * self.len + b as usize]
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 struct PixelWithDistances {
-    pixel: u32,
     colour_distance_up_left: f32, // X: Colour distance to the pixel to the up-left.
     colour_distance_up: f32, // Y in the shader.
     colour_distance_up_right: f32, // Z.
@@ -103,13 +380,20 @@ struct PixelWithDistances {
 impl PixelWithDistances {
     fn offscreen() -> Self { // The representation for a transparent offscreen pixel.
         Self {
-            pixel: 0,
             colour_distance_up_left: 1.,
             colour_distance_up: 1.,
             colour_distance_up_right: 1.,
             colour_distance_right: 1.,
         }
     }
+    fn max(self, other: &Self) -> Self {
+        Self {
+            colour_distance_up_left: self.colour_distance_up_left.max(other.colour_distance_up_left),
+            colour_distance_up: self.colour_distance_up.max(other.colour_distance_up),
+            colour_distance_up_right: self.colour_distance_up_right.max(other.colour_distance_up_right),
+            colour_distance_right: self.colour_distance_right.max(other.colour_distance_right),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -122,28 +406,20 @@ struct ImageWithDistances {
 // Calculate the colour distances to neighbours.
 // This implements pass 0 here:
 // https://github.com/libretro/slang-shaders/blob/master/edge-smoothing/scalefx/shaders/scalefx-pass0.slang
-fn calculate_distances(image: &Image) -> ImageWithDistances {
-    let mut pixels: Vec<PixelWithDistances> = Vec::with_capacity(image.pixels.len());
-    for y in 0..image.height {
-        for x in 0..image.width {
-            let i = y * image.width + x;
-
-            // Get the neighbouring pixels, returning transparent if they're out of bounds.
-            let up_left: u32 = if y==0 || x==0 { 0 } else { image.pixels[i - image.width - 1] };
-            let up: u32 = if y==0 { 0 } else { image.pixels[i - image.width] };
-            let up_right: u32 = if y==0 || x==image.width-1 { 0 } else { image.pixels[i - image.width + 1] };
-            let center = image.pixels[i];
-            let right = if x==image.width-1 { 0 } else { image.pixels[i + 1] };
-
-            pixels.push(PixelWithDistances {
-                pixel: center,
-                colour_distance_up_left: colour_distance(center, up_left),
-                colour_distance_up: colour_distance(center, up),
-                colour_distance_up_right: colour_distance(center, up_right),
-                colour_distance_right: colour_distance(center, right),
-            });
+// If there's a distance table, the pixels are indices into it, and the distances are looked up rather than calculated.
+fn calculate_distances(image: &Image, table: Option<&DistanceTable>, options: &ScaleFxOptions) -> ImageWithDistances {
+    let is_simd = options.simd && options.colour_metric == ColourMetric::Redmean && options.alpha_mode == AlphaMode::Binary && simd::is_supported();
+    let (metric, alpha_mode, alpha_cutoff) = (options.colour_metric, options.alpha_mode, options.alpha_cutoff);
+    let pixels = map_rows(image.height, image.width, options.thread_count(), |y, pixels: &mut Vec<PixelWithDistances>| {
+        if let Some(table) = table {
+            calculate_distances_row(image, y, pixels, |center, others| others.map(|other| table.distance(center, other)));
+        } else if is_simd {
+            // Safety: is_simd is only true when simd::is_supported.
+            unsafe { simd::calculate_distances_row(image, y, pixels, alpha_cutoff) }
+        } else {
+            calculate_distances_row(image, y, pixels, |center, others| others.map(|other| metric.distance_with_alpha(center, other, alpha_mode, alpha_cutoff)));
         }
-    }
+    });
     ImageWithDistances {
         width: image.width,
         height: image.height,
@@ -151,31 +427,42 @@ fn calculate_distances(image: &Image) -> ImageWithDistances {
     }
 }
 
+// Calculates one row of pass 0, using the given function to find the distances from a pixel to four others.
+#[inline(always)]
+fn calculate_distances_row(image: &Image, y: usize, pixels: &mut Vec<PixelWithDistances>, colour_distances: impl Fn(u32, [u32; 4]) -> [f32; 4]) {
+    for x in 0..image.width {
+        let i = y * image.width + x;
+
+        // Get the neighbouring pixels, returning transparent if they're out of bounds.
+        let up_left: u32 = if y==0 || x==0 { 0 } else { image.pixels[i - image.width - 1] };
+        let up: u32 = if y==0 { 0 } else { image.pixels[i - image.width] };
+        let up_right: u32 = if y==0 || x==image.width-1 { 0 } else { image.pixels[i - image.width + 1] };
+        let center = image.pixels[i];
+        let right = if x==image.width-1 { 0 } else { image.pixels[i + 1] };
+
+        let [up_left, up, up_right, right] = colour_distances(center, [up_left, up, up_right, right]);
+        pixels.push(PixelWithDistances {
+            colour_distance_up_left: up_left,
+            colour_distance_up: up,
+            colour_distance_up_right: up_right,
+            colour_distance_right: right,
+        });
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
-struct PixelWithCornerStrengths {
-    pixel: u32,
-    colour_distance_up_left: f32, // Colour distance to the pixel to the up-left.
-    colour_distance_up: f32,
-    colour_distance_up_right: f32,
-    colour_distance_right: f32,
-    corner_strength_up_left: f32, // Corner strength. Called X in the shader.
-    corner_strength_up_right: f32, // Y in the shader.
-    corner_strength_down_right: f32, // Z in the shader.
-    corner_strength_down_left: f32, // W in the shader.
-}
-impl PixelWithCornerStrengths {
+struct CornerStrengths {
+    up_left: f32, // Corner strength. Called X in the shader.
+    up_right: f32, // Y in the shader.
+    down_right: f32, // Z in the shader.
+    down_left: f32, // W in the shader.
+}
+impl CornerStrengths {
     fn offscreen() -> Self { // The representation for a transparent offscreen pixel.
-        Self {
-            pixel: 0,
-            colour_distance_up_left: 1.,
-            colour_distance_up: 1.,
-            colour_distance_up_right: 1.,
-            colour_distance_right: 1.,
-            corner_strength_up_left: 0.,
-            corner_strength_up_right: 0.,
-            corner_strength_down_right: 0.,
-            corner_strength_down_left: 0.,
-        }
+        Self { up_left: 0., up_right: 0., down_right: 0., down_left: 0. }
+    }
+    fn is_zero(&self) -> bool {
+        self.up_left == 0. && self.up_right == 0. && self.down_right == 0. && self.down_left == 0.
     }
 }
 
@@ -183,61 +470,36 @@ impl PixelWithCornerStrengths {
 struct ImageWithCornerStrengths {
     width: usize,
     height: usize,
-    pixels: Vec<PixelWithCornerStrengths>,
+    pixels: Vec<CornerStrengths>,
 }
 
 // Calculate all the corner strengths.
 // Aka "calculate strength of interpolation candidates" according to the shader comment.
 // This implements pass 1 here:
 // https://github.com/libretro/slang-shaders/blob/master/edge-smoothing/scalefx/shaders/scalefx-pass1.slang
-fn calculate_corner_strengths(image: &ImageWithDistances) -> ImageWithCornerStrengths {
+fn calculate_corner_strengths(image: &ImageWithDistances, options: &ScaleFxOptions) -> ImageWithCornerStrengths {
 
-    fn corner_strength(d: f32, a_x: f32, a_y: f32, b_x: f32, b_y: f32) -> f32 {
+    let corner_strength = |d: f32, a_x: f32, a_y: f32, b_x: f32, b_y: f32| -> f32 {
         let diff = a_x - a_y;
-        let weight_1 = (THRESHOLD - d).max(0.) / THRESHOLD;
+        let weight_1 = (options.threshold - d).max(0.) / options.threshold;
         let is_x_g_y = a_x.min(b_x) + a_x  >  a_y.min(b_y) + a_y;
         let x_g_y_diff = if is_x_g_y { diff } else { -diff };
         let weight_2_raw = (1. - d) + x_g_y_diff;
         let weight_2 = weight_2_raw.clamp(0., 1.);
-        if IS_FILTER_AA_ENABLED || 2. * d < a_x + a_y { weight_1 * weight_2 * a_x * a_y } else { 0. }
-    }
-
-    let mut pixels: Vec<PixelWithCornerStrengths> = Vec::with_capacity(image.pixels.len());
-    let offscreen = PixelWithDistances::offscreen();
-
-    for y in 0..image.height {
-        for x in 0..image.width {
-            let i = y * image.width + x;
-
-            // Get the neighbouring pixels, returning transparent if they're out of bounds.
-            let up_left = if y==0 || x==0 { offscreen } else { image.pixels[i - image.width - 1] };
-            let up = if y==0 { offscreen } else { image.pixels[i - image.width] };
-            let left = if x==0 { offscreen } else { image.pixels[i - 1] };
-            let center = image.pixels[i];
-            let right = if x==image.width-1 { offscreen } else { image.pixels[i + 1] };
-            let down_left = if x==0 || y==image.height-1 { offscreen } else { image.pixels[i + image.width - 1] };
-            let down = if y==image.height-1 { offscreen } else { image.pixels[i + image.width] };
-            let down_right = if x==image.width-1 || y==image.height-1 { offscreen } else { image.pixels[i + image.width + 1] };
-
-            // Calculate the corner strengths:
-            let up_left = corner_strength(left.colour_distance_up_right, left.colour_distance_right, center.colour_distance_up, up_left.colour_distance_right, left.colour_distance_up);
-            let up_right = corner_strength(right.colour_distance_up_left, center.colour_distance_right, center.colour_distance_up, up.colour_distance_right, right.colour_distance_up);
-            let down_right = corner_strength(down.colour_distance_up_right, center.colour_distance_right, down.colour_distance_up, down.colour_distance_right, down_right.colour_distance_up);
-            let down_left = corner_strength(down.colour_distance_up_left, left.colour_distance_right, down.colour_distance_up, down_left.colour_distance_right, down_left.colour_distance_up);
-
-            pixels.push(PixelWithCornerStrengths {
-                pixel: center.pixel,
-                colour_distance_up_left: center.colour_distance_up_left,
-                colour_distance_up: center.colour_distance_up,
-                colour_distance_up_right: center.colour_distance_up_right,
-                colour_distance_right: center.colour_distance_right,
-                corner_strength_up_left: up_left,
-                corner_strength_up_right: up_right,
-                corner_strength_down_right: down_right,
-                corner_strength_down_left: down_left,
+        if options.is_filter_aa_enabled || 2. * d < a_x + a_y { weight_1 * weight_2 * a_x * a_y } else { 0. }
+    };
+
+    let is_simd = options.simd && simd::is_supported();
+    let pixels = map_rows(image.height, image.width, options.thread_count(), |y, pixels: &mut Vec<CornerStrengths>| {
+        if is_simd {
+            // Safety: is_simd is only true when simd::is_supported.
+            unsafe { simd::calculate_corner_strengths_row(image, y, pixels, options) }
+        } else {
+            calculate_corner_strengths_row(image, y, pixels, |d, a_x, a_y, b_x, b_y| {
+                std::array::from_fn(|i| corner_strength(d[i], a_x[i], a_y[i], b_x[i], b_y[i]))
             });
         }
-    }
+    });
     ImageWithCornerStrengths {
         width: image.width,
         height: image.height,
@@ -245,24 +507,51 @@ fn calculate_corner_strengths(image: &ImageWithDistances) -> ImageWithCornerStre
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct PixelWithCornerConfiguration {
-    pixel: u32,
-    res: BVec4, // Resolution?
-    horizontal_edges: BVec4,
-    vertical_edges: BVec4,
-    orientation: BVec4,
+// Calculates one row of pass 1, using the given function to find all four corner strengths of a pixel at once.
+// Its arguments are the corner_strength arguments for the up-left, up-right, down-right and down-left corners.
+#[inline(always)]
+fn calculate_corner_strengths_row(image: &ImageWithDistances, y: usize, pixels: &mut Vec<CornerStrengths>, corner_strengths: impl Fn([f32; 4], [f32; 4], [f32; 4], [f32; 4], [f32; 4]) -> [f32; 4]) {
+    let offscreen = PixelWithDistances::offscreen();
+    for x in 0..image.width {
+        let i = y * image.width + x;
+
+        // Get the neighbouring pixels, returning transparent if they're out of bounds.
+        let up_left = if y==0 || x==0 { offscreen } else { image.pixels[i - image.width - 1] };
+        let up = if y==0 { offscreen } else { image.pixels[i - image.width] };
+        let left = if x==0 { offscreen } else { image.pixels[i - 1] };
+        let center = image.pixels[i];
+        let right = if x==image.width-1 { offscreen } else { image.pixels[i + 1] };
+        let down_left = if x==0 || y==image.height-1 { offscreen } else { image.pixels[i + image.width - 1] };
+        let down = if y==image.height-1 { offscreen } else { image.pixels[i + image.width] };
+        let down_right = if x==image.width-1 || y==image.height-1 { offscreen } else { image.pixels[i + image.width + 1] };
+
+        // Calculate the corner strengths:
+        let [up_left, up_right, down_right, down_left] = corner_strengths(
+            [left.colour_distance_up_right, right.colour_distance_up_left, down.colour_distance_up_right, down.colour_distance_up_left],
+            [left.colour_distance_right, center.colour_distance_right, center.colour_distance_right, left.colour_distance_right],
+            [center.colour_distance_up, center.colour_distance_up, down.colour_distance_up, down.colour_distance_up],
+            [up_left.colour_distance_right, up.colour_distance_right, down.colour_distance_right, down_left.colour_distance_right],
+            [left.colour_distance_up, right.colour_distance_up, down_right.colour_distance_up, down_left.colour_distance_up]);
+
+        pixels.push(CornerStrengths { up_left, up_right, down_right, down_left });
+    }
 }
+
+// Pass 2's four flag vectors, packed a nibble each (x in the lowest bit):
+// res (resolution?), then horizontal edges, vertical edges, and orientation.
+#[derive(Debug, Clone, Copy)]
+struct PixelWithCornerConfiguration(u16);
 impl PixelWithCornerConfiguration {
+    fn new(res: BVec4, horizontal_edges: BVec4, vertical_edges: BVec4, orientation: BVec4) -> Self {
+        Self(res.to_bits() as u16 | (horizontal_edges.to_bits() as u16) << 4 | (vertical_edges.to_bits() as u16) << 8 | (orientation.to_bits() as u16) << 12)
+    }
     fn offscreen() -> Self {
-        Self {
-            pixel: 0,
-            res: BVec4::zero(),
-            horizontal_edges: BVec4::zero(),
-            vertical_edges: BVec4::zero(),
-            orientation: BVec4::zero(),
-        }
+        Self(0)
     }
+    fn res(self) -> BVec4 { BVec4::from_bits(self.0 as u8) }
+    fn horizontal_edges(self) -> BVec4 { BVec4::from_bits((self.0 >> 4) as u8) }
+    fn vertical_edges(self) -> BVec4 { BVec4::from_bits((self.0 >> 8) as u8) }
+    fn orientation(self) -> BVec4 { BVec4::from_bits((self.0 >> 12) as u8) }
 }
 
 #[derive(Debug)]
@@ -272,25 +561,101 @@ struct ImageWithCornerConfigurations {
     pixels: Vec<PixelWithCornerConfiguration>,
 }
 
-// Resolve ambiguous configurations of corner candidates at pixel junctions.
-// This implements pass 2 here:
+// The corner votes and strengths where four pixels meet.
+// Each junction is shared by the four pixels around it, so pass 2 calculates them once up front
+// rather than four times over. All that pass 2 needs of each is a handful of flags, packed a nibble each (x in the lowest bit):
+// the majority votes (jx in the shader), which strengths are non-zero, then whether the x+z or y+w strengths are greater.
+#[derive(Debug, Clone, Copy)]
+struct Junction(u16);
+impl Junction {
+    const EMPTY: Self = Self(0); // What four zero strengths resolve to: no flags at all.
+    fn votes(self) -> Vec4 { BVec4::from_bits(self.0 as u8).to_vec() }
+    fn has_strength(self) -> Vec4 { BVec4::from_bits((self.0 >> 4) as u8).to_vec() }
+    fn xz_stronger(self) -> f32 { ((self.0 >> 8) & 1) as f32 }
+    fn yw_stronger(self) -> f32 { ((self.0 >> 9) & 1) as f32 }
+}
+
+// Calculates the junction at the top-left of every pixel, including the junctions along the right and bottom edges.
+// This is the first half of pass 2 here:
 // https://github.com/libretro/slang-shaders/blob/master/edge-smoothing/scalefx/shaders/scalefx-pass2.slang
-fn resolve_corner_configurations(image: &ImageWithCornerStrengths) -> ImageWithCornerConfigurations {
+fn calculate_junctions(image: &ImageWithCornerStrengths, options: &ScaleFxOptions) -> Vec<Junction> {
+    let offscreen = CornerStrengths::offscreen();
+    let junctions_width = image.width + 1;
+
+    map_rows(image.height + 1, junctions_width, options.thread_count(), |y, junctions: &mut Vec<Junction>| {
+        for x in 0..junctions_width {
+            // Get the four pixels around the junction, returning transparent if they're out of bounds.
+            // Grid: A B
+            //        +   (The junction is at the top-left of E)
+            //       D E
+            let is_top = y==0;
+            let is_left = x==0;
+            let is_bottom = y==image.height;
+            let is_right = x==image.width;
+            let index = y * image.width + x;
+            let a = if is_top || is_left { offscreen } else { image.pixels[index - image.width - 1] };
+            let b = if is_top || is_right { offscreen } else { image.pixels[index - image.width] };
+            let d = if is_bottom || is_left { offscreen } else { image.pixels[index - 1] };
+            let e = if is_bottom || is_right { offscreen } else { image.pixels[index] };
+
+            // With no strengths at all nothing can win a vote, so flat areas can skip the maths below.
+            if a.is_zero() && b.is_zero() && d.is_zero() && e.is_zero() {
+                junctions.push(Junction::EMPTY);
+                continue;
+            }
+
+            // Strength junction:
+            let js = Vec4{x: a.down_right, y: b.down_left, z: e.up_left, w: d.up_right};
+            let (_, j) = junction_votes(&a, &b, &d, &e);
+
+            let zero4 = Vec4::zero();
+            let has_strength = Vec4::ge(js, zero4);
+            let xz_stronger = ge_f32(js.x + js.z, js.y + js.w);
+            let yw_stronger = ge_f32(js.y + js.w, js.x + js.z);
+            junctions.push(Junction(j.to_bvec().to_bits() as u16 | (has_strength.to_bvec().to_bits() as u16) << 4 | (xz_stronger as u16) << 8 | (yw_stronger as u16) << 9));
+        }
+    })
+}
+
+// The dominance of each of the four corners meeting at a junction, and the majority vote for which of them win.
+// A, B, E and D are the pixels up-left, up-right, down-right and down-left of the junction, as per calculate_junctions.
+#[inline(always)]
+fn junction_votes(a: &CornerStrengths, b: &CornerStrengths, d: &CornerStrengths, e: &CornerStrengths) -> (Vec4, Vec4) {
 
     // Calculate corner dominance at junctions:
     fn corner_dominance(x: &Vec3, y: &Vec3, z: &Vec3, w: &Vec3) -> Vec4 {
         2.0f32 * Vec4{x: x.y, y: y.y, z: z.y, w: w.y} - (Vec4{x: x.x, y: y.x, z: z.x, w: w.x} + Vec4{x: x.z, y: y.z, z: z.z, w: w.z})
     }
 
+    // Dominance junction:
+    let dominance_junction = corner_dominance(
+        &Vec3 { x: a.up_right, y: a.down_right, z: a.down_left },
+        &Vec3 { x: b.down_right, y: b.down_left, z: b.up_left},
+        &Vec3 { x: e.down_left, y: e.up_left, z: e.up_right},
+        &Vec3 { x: d.up_left, y: d.up_right, z: d.down_right});
+
+    // Majority vote for ambiguous dominance junctions:
+    let zero4 = Vec4::zero();
+    let j = (Vec4::ge(dominance_junction, zero4) * (Vec4::leq(dominance_junction.yzwx(), zero4) * Vec4::leq(dominance_junction.wxyz(), zero4) + Vec4::ge(dominance_junction + dominance_junction.zwxy(), dominance_junction.yzwx() + dominance_junction.wxyz()))).min(1.);
+    (dominance_junction, j)
+}
+
+// Resolve ambiguous configurations of corner candidates at pixel junctions.
+// This implements the rest of pass 2 here:
+// https://github.com/libretro/slang-shaders/blob/master/edge-smoothing/scalefx/shaders/scalefx-pass2.slang
+fn resolve_corner_configurations(distances: &ImageWithDistances, corners: &ImageWithCornerStrengths, options: &ScaleFxOptions) -> ImageWithCornerConfigurations {
+
     // Necessary but not sufficient junction condition for orthogonal edges.
     fn clear(crn: Vec2, a: Vec2, b: Vec2) -> f32 {
         if crn.x >= a.x.min(a.y).max(b.x.min(b.y)) && crn.y >= a.x.min(b.y).max(b.x.min(a.y)) { 1. } else { 0. }
     }
 
-    let mut pixels: Vec<PixelWithCornerConfiguration> = Vec::with_capacity(image.pixels.len());
-    let offscreen = PixelWithCornerStrengths::offscreen();
+    let junctions = calculate_junctions(corners, options);
+    let junctions_width = corners.width + 1;
+    let offscreen = PixelWithDistances::offscreen();
+    let image = distances;
 
-    for y in 0..image.height {
+    let pixels = map_rows(image.height, image.width, options.thread_count(), |y, pixels: &mut Vec<PixelWithCornerConfiguration>| {
         for x in 0..image.width {
             let index = y * image.width + x;
 
@@ -304,7 +669,6 @@ fn resolve_corner_configurations(image: &ImageWithCornerStrengths) -> ImageWithC
             let is_right = x>=image.width-1;
             let a = if is_top || is_left { offscreen } else { image.pixels[index - image.width - 1] };
             let b = if is_top { offscreen } else { image.pixels[index - image.width] };
-            let c = if is_top || is_right { offscreen } else { image.pixels[index - image.width + 1] };
             let d = if is_left { offscreen } else { image.pixels[index - 1] };
             let e = image.pixels[index];
             let f = if is_right { offscreen } else { image.pixels[index + 1] };
@@ -312,46 +676,25 @@ fn resolve_corner_configurations(image: &ImageWithCornerStrengths) -> ImageWithC
             let h = if is_bottom { offscreen } else { image.pixels[index + image.width] };
             let i = if is_bottom || is_right { offscreen } else { image.pixels[index + image.width + 1] };
 
-            // Strength junctions:
-            let jsx = Vec4{x: a.corner_strength_down_right, y: b.corner_strength_down_left, z: e.corner_strength_up_left, w: d.corner_strength_up_right};
-            let jsy = Vec4{x: b.corner_strength_down_right, y: c.corner_strength_down_left, z: f.corner_strength_up_left, w: e.corner_strength_up_right};
-            let jsz = Vec4{x: e.corner_strength_down_right, y: f.corner_strength_down_left, z: i.corner_strength_up_left, w: h.corner_strength_up_right};
-            let jsw = Vec4{x: d.corner_strength_down_right, y: e.corner_strength_down_left, z: h.corner_strength_up_left, w: g.corner_strength_up_right};
-
-            // Dominance junctions:
-            let dominance_junction_x = corner_dominance(
-                &Vec3 { x: a.corner_strength_up_right, y: a.corner_strength_down_right, z: a.corner_strength_down_left },
-                &Vec3 { x: b.corner_strength_down_right, y: b.corner_strength_down_left, z: b.corner_strength_up_left},
-                &Vec3 { x: e.corner_strength_down_left, y: e.corner_strength_up_left, z: e.corner_strength_up_right},
-                &Vec3 { x: d.corner_strength_up_left, y: d.corner_strength_up_right, z: d.corner_strength_down_right});
-            let dominance_junction_y = corner_dominance(
-                &Vec3 { x: b.corner_strength_up_right, y: b.corner_strength_down_right, z: b.corner_strength_down_left },
-                &Vec3 { x: c.corner_strength_down_right, y: c.corner_strength_down_left, z: c.corner_strength_up_left},
-                &Vec3 { x: f.corner_strength_down_left, y: f.corner_strength_up_left, z: f.corner_strength_up_right},
-                &Vec3 { x: e.corner_strength_up_left, y: e.corner_strength_up_right, z: e.corner_strength_down_right});
-            let dominance_junction_z = corner_dominance(
-                &Vec3 { x: e.corner_strength_up_right, y: e.corner_strength_down_right, z: e.corner_strength_down_left },
-                &Vec3 { x: f.corner_strength_down_right, y: f.corner_strength_down_left, z: f.corner_strength_up_left},
-                &Vec3 { x: i.corner_strength_down_left, y: i.corner_strength_up_left, z: i.corner_strength_up_right},
-                &Vec3 { x: h.corner_strength_up_left, y: h.corner_strength_up_right, z: h.corner_strength_down_right});
-            let dominance_junction_w = corner_dominance(
-                &Vec3 { x: d.corner_strength_up_right, y: d.corner_strength_down_right, z: d.corner_strength_down_left },
-                &Vec3 { x: e.corner_strength_down_right, y: e.corner_strength_down_left, z: e.corner_strength_up_left},
-                &Vec3 { x: h.corner_strength_down_left, y: h.corner_strength_up_left, z: h.corner_strength_up_right},
-                &Vec3 { x: g.corner_strength_up_left, y: g.corner_strength_up_right, z: g.corner_strength_down_right});
-
-            // Majority vote for ambiguous dominance junctions:
-            let zero4 = Vec4::zero();
-            let jx = (Vec4::ge(dominance_junction_x, zero4) * (Vec4::leq(dominance_junction_x.yzwx(), zero4) * Vec4::leq(dominance_junction_x.wxyz(), zero4) + Vec4::ge(dominance_junction_x + dominance_junction_x.zwxy(), dominance_junction_x.yzwx() + dominance_junction_x.wxyz()))).min(1.);
-            let jy = (Vec4::ge(dominance_junction_y, zero4) * (Vec4::leq(dominance_junction_y.yzwx(), zero4) * Vec4::leq(dominance_junction_y.wxyz(), zero4) + Vec4::ge(dominance_junction_y + dominance_junction_y.zwxy(), dominance_junction_y.yzwx() + dominance_junction_y.wxyz()))).min(1.);
-            let jz = (Vec4::ge(dominance_junction_z, zero4) * (Vec4::leq(dominance_junction_z.yzwx(), zero4) * Vec4::leq(dominance_junction_z.wxyz(), zero4) + Vec4::ge(dominance_junction_z + dominance_junction_z.zwxy(), dominance_junction_z.yzwx() + dominance_junction_z.wxyz()))).min(1.);
-            let jw = (Vec4::ge(dominance_junction_w, zero4) * (Vec4::leq(dominance_junction_w.yzwx(), zero4) * Vec4::leq(dominance_junction_w.wxyz(), zero4) + Vec4::ge(dominance_junction_w + dominance_junction_w.zwxy(), dominance_junction_w.yzwx() + dominance_junction_w.wxyz()))).min(1.);
+            // The junctions at each corner of E: top-left, top-right, bottom-right, bottom-left.
+            let junction_x = junctions[y * junctions_width + x];
+            let junction_y = junctions[y * junctions_width + x + 1];
+            let junction_z = junctions[(y + 1) * junctions_width + x + 1];
+            let junction_w = junctions[(y + 1) * junctions_width + x];
+            let jx = junction_x.votes();
+            let jy = junction_y.votes();
+            let jz = junction_z.votes();
+            let jw = junction_w.votes();
+            let jsx = junction_x.has_strength();
+            let jsy = junction_y.has_strength();
+            let jsz = junction_z.has_strength();
+            let jsw = junction_w.has_strength();
 
             // Inject strength without creating new contradictions:
-            let res_x = (jx.z + (1. - jx.y) * (1. - jx.w) * ge_f32(jsx.z, 0.) * (jx.x + ge_f32(jsx.x + jsx.z, jsx.y + jsx.w))).min(1.);
-            let res_y = (jy.w + (1. - jy.z) * (1. - jy.x) * ge_f32(jsy.w, 0.) * (jy.y + ge_f32(jsy.y + jsy.w, jsy.x + jsy.z))).min(1.);
-            let res_z = (jz.x + (1. - jz.w) * (1. - jz.y) * ge_f32(jsz.x, 0.) * (jz.z + ge_f32(jsz.x + jsz.z, jsz.y + jsz.w))).min(1.);
-            let res_w = (jw.y + (1. - jw.x) * (1. - jw.z) * ge_f32(jsw.y, 0.) * (jw.w + ge_f32(jsw.y + jsw.w, jsw.x + jsw.z))).min(1.);
+            let res_x = (jx.z + (1. - jx.y) * (1. - jx.w) * jsx.z * (jx.x + junction_x.xz_stronger())).min(1.);
+            let res_y = (jy.w + (1. - jy.z) * (1. - jy.x) * jsy.w * (jy.y + junction_y.yw_stronger())).min(1.);
+            let res_z = (jz.x + (1. - jz.w) * (1. - jz.y) * jsz.x * (jz.z + junction_z.xz_stronger())).min(1.);
+            let res_w = (jw.y + (1. - jw.x) * (1. - jw.z) * jsw.y * (jw.w + junction_w.yw_stronger())).min(1.);
             let res_early = Vec4{ x: res_x, y: res_y, z: res_z, w: res_w };
 
             // Single pixel & end of line detection:
@@ -383,15 +726,9 @@ fn resolve_corner_configurations(image: &ImageWithCornerStrengths) -> ImageWithC
             let horizontal_edges = Vec4::le(ho, v) * clr;
             let vertical_edges = Vec4::ge(ho, v) * clr;
 
-            pixels.push(PixelWithCornerConfiguration {
-                pixel: e.pixel,
-                res: res.to_bvec(),
-                horizontal_edges: horizontal_edges.to_bvec(),
-                vertical_edges: vertical_edges.to_bvec(),
-                orientation: orientation.to_bvec(),
-            });
+            pixels.push(PixelWithCornerConfiguration::new(res.to_bvec(), horizontal_edges.to_bvec(), vertical_edges.to_bvec(), orientation.to_bvec()));
         }
-    }
+    });
 
     ImageWithCornerConfigurations {
         width: image.width,
@@ -400,11 +737,37 @@ fn resolve_corner_configurations(image: &ImageWithCornerStrengths) -> ImageWithC
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct PixelWithEdgeLevel {
-    pixel: u32,
-    corners: U8Vec4,
-    mids: U8Vec4,
+// Pass 3's subpixel tags, packed a nibble each (x in the lowest nibble): the corners, then the mids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PixelWithEdgeLevel(u32);
+impl PixelWithEdgeLevel {
+    fn new(corners: U8Vec4, mids: U8Vec4) -> Self {
+        Self(corners.to_nibbles() as u32 | (mids.to_nibbles() as u32) << 16)
+    }
+    fn flat() -> Self { // Every subpixel is E.
+        Self(0)
+    }
+    fn is_flat(self) -> bool { self.0 == 0 }
+    fn is_valid(self) -> bool { (0..8).all(|nibble| (self.0 >> (nibble * 4)) & 0xf <= 8) } // Tags only go up to 8.
+    fn corners(self) -> U8Vec4 { U8Vec4::from_nibbles(self.0 as u16) }
+    fn mids(self) -> U8Vec4 { U8Vec4::from_nibbles((self.0 >> 16) as u16) }
+    // The tag for the subpixel at the given position in this pixel's 3x3 block.
+    fn tag(self, spx: usize, spy: usize) -> u8 {
+        let mid = self.mids();
+        let crn = self.corners();
+        match (spx, spy) {
+            (0, 0) => crn.x,
+            (1, 0) => mid.x,
+            (2, 0) => crn.y,
+            (0, 1) => mid.w,
+            (1, 1) => 0,
+            (2, 1) => mid.y,
+            (0, 2) => crn.w,
+            (1, 2) => mid.z,
+            (2, 2) => crn.z,
+            _ => 0,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -416,99 +779,20 @@ struct ImageWithEdgeLevels {
 // Determines which edge level is present and prepares tags for subpixel output in the final pass.
 // This implements pass 3 here:
 // https://github.com/libretro/slang-shaders/blob/master/edge-smoothing/scalefx/shaders/scalefx-pass3.slang
-fn determine_edge_levels(image: &ImageWithCornerConfigurations) -> ImageWithEdgeLevels {
-    let mut pixels: Vec<PixelWithEdgeLevel> = Vec::with_capacity(image.pixels.len());
-    let offscreen = PixelWithCornerConfiguration::offscreen();
-
-    for y in 0..image.height {
+fn determine_edge_levels(image: &ImageWithCornerConfigurations, options: &ScaleFxOptions) -> ImageWithEdgeLevels {
+    let pixels = map_rows(image.height, image.width, options.thread_count(), |y, pixels: &mut Vec<PixelWithEdgeLevel>| {
         for x in 0..image.width {
-            // Get the neighbouring pixels, returning transparent if they're out of bounds.
-            // Grid:
-            //         B1
-            //         B0
-            //         B
-            // D1 D0 D E F F0 F1  (E is the current pixel)
-            //         H
-            //         H0
-            //         H1
-            let index = y * image.width + x;
-            let b1 = if y<=2 { offscreen } else { image.pixels[index - image.width * 3] };
-            let b0 = if y<=1 { offscreen } else { image.pixels[index - image.width * 2] };
-            let b = if y<=0 { offscreen } else { image.pixels[index - image.width] };
-            let d = if x<=0 { offscreen } else { image.pixels[index - 1] };
-            let d0 = if x<=1 { offscreen } else { image.pixels[index - 2] };
-            let d1 = if x<=2 { offscreen } else { image.pixels[index - 3] };
-            let e = image.pixels[index];
-            let f = if x+1 >= image.width { offscreen } else { image.pixels[index + 1] };
-            let f0 = if x+2 >= image.width { offscreen } else { image.pixels[index + 2] };
-            let f1 = if x+3 >= image.width { offscreen } else { image.pixels[index + 3] };
-            let h = if y+1 >= image.height { offscreen } else { image.pixels[index + image.width] };
-            let h0 = if y+2 >= image.height { offscreen } else { image.pixels[index + image.width*2] };
-            let h1 = if y+3 >= image.height { offscreen } else { image.pixels[index + image.width*3] };
-
-            // Extract data:            
-            let ec = e.res; let eh = e.horizontal_edges; let ev = e.vertical_edges; let eo = e.orientation;
-            let dc = d.res; let dh = d.horizontal_edges; let dr = d.orientation; let d0c = d0.res; let d0h = d0.horizontal_edges; let d1h = d1.horizontal_edges;
-            let fc = f.res; let fh = f.horizontal_edges; let fo = f.orientation; let f0c = f0.res; let f0h = f0.horizontal_edges; let f1h = f1.horizontal_edges;
-            let bc = b.res; let bv = b.vertical_edges; let bo = b.orientation; let b0c = b0.res; let b0v = b0.vertical_edges; let b1v = b1.vertical_edges;
-            let hc = h.res; let hv = h.vertical_edges; let ho = h.orientation; let h0c = h0.res; let h0v = h0.vertical_edges; let h1v = h1.vertical_edges;
-
-            // Level 1 corners (horizontal, vertical):
-            let lvl1x = ec.x && (dc.z || bc.z || FILTER_CORNERS);
-            let lvl1y = ec.y && (fc.w || bc.w || FILTER_CORNERS);
-            let lvl1z = ec.z && (fc.x || hc.x || FILTER_CORNERS);
-            let lvl1w = ec.w && (dc.y || hc.y || FILTER_CORNERS);
-
-            // Level 2 mid (left, right / up, down):
-            let lvl2x = BVec2{ x: (ec.x && eh.y) && dc.z, y: (ec.y && eh.x) && fc.w };
-            let lvl2y = BVec2{ x: (ec.y && ev.z) && bc.w, y: (ec.z && ev.y) && hc.x };
-            let lvl2z = BVec2{ x: (ec.w && eh.z) && dc.y, y: (ec.z && eh.w) && fc.x };
-            let lvl2w = BVec2{ x: (ec.x && ev.w) && bc.z, y: (ec.w && ev.x) && hc.y };
-
-            // Level 3 corners (horizontal, vertical):
-            let lvl3x = BVec2{ x: lvl2x.y && (dh.y && dh.x) && fh.z, y: lvl2w.y && (bv.w && bv.x) && hv.z };
-            let lvl3y = BVec2{ x: lvl2x.x && (fh.x && fh.y) && dh.w, y: lvl2y.y && (bv.z && bv.y) && hv.w };
-            let lvl3z = BVec2{ x: lvl2z.x && (fh.w && fh.z) && dh.x, y: lvl2y.x && (hv.y && hv.z) && bv.x };
-            let lvl3w = BVec2{ x: lvl2z.y && (dh.z && dh.w) && fh.y, y: lvl2w.x && (hv.x && hv.w) && bv.y };
-
-            // Level 4 corners (horizontal, vertical):
-            let lvl4x = BVec2{ x: (dc.x && dh.y && eh.x && eh.y && fh.x && fh.y) && (d0c.z && d0h.w), y: (bc.x && bv.w && ev.x && ev.w && hv.x && hv.w) && (b0c.z && b0v.y) };
-            let lvl4y = BVec2{ x: (fc.y && fh.x && eh.y && eh.x && dh.y && dh.x) && (f0c.w && f0h.z), y: (bc.y && bv.z && ev.y && ev.z && hv.y && hv.z) && (b0c.w && b0v.x) };
-            let lvl4z = BVec2{ x: (fc.z && fh.w && eh.z && eh.w && dh.z && dh.w) && (f0c.x && f0h.y), y: (hc.z && hv.y && ev.z && ev.y && bv.z && bv.y) && (h0c.x && h0v.w) };
-            let lvl4w = BVec2{ x: (dc.w && dh.z && eh.w && eh.z && fh.w && fh.z) && (d0c.y && d0h.x), y: (hc.w && hv.x && ev.w && ev.x && bv.w && bv.x) && (h0c.y && h0v.z) };
-
-            // Level 5 mid (left, right / up, down):
-            let lvl5x = BVec2{ x: lvl4x.x && (f0h.x && f0h.y) && (d1h.z && d1h.w), y: lvl4y.x && (d0h.y && d0h.x) && (f1h.w && f1h.z) };
-            let lvl5y = BVec2{ x: lvl4y.y && (h0v.y && h0v.z) && (b1v.w && b1v.x), y: lvl4z.y && (b0v.z && b0v.y) && (h1v.x && h1v.w) };
-            let lvl5z = BVec2{ x: lvl4w.x && (f0h.w && f0h.z) && (d1h.y && d1h.x), y: lvl4z.x && (d0h.z && d0h.w) && (f1h.x && f1h.y) };
-            let lvl5w = BVec2{ x: lvl4x.y && (h0v.x && h0v.w) && (b1v.z && b1v.y), y: lvl4w.y && (b0v.w && b0v.x) && (h1v.y && h1v.z) };
-
-            // Level 6 corners (horizontal, vertical):
-            let lvl6x = BVec2{ x: lvl5x.y && (d1h.y && d1h.x), y: lvl5w.y && (b1v.w && b1v.x) };
-            let lvl6y = BVec2{ x: lvl5x.x && (f1h.x && f1h.y), y: lvl5y.y && (b1v.z && b1v.y) };
-            let lvl6z = BVec2{ x: lvl5z.x && (f1h.w && f1h.z), y: lvl5y.x && (h1v.y && h1v.z) };
-            let lvl6w = BVec2{ x: lvl5z.y && (d1h.z && d1h.w), y: lvl5w.x && (h1v.x && h1v.w) };
-
-            // Subpixels - 0 = E, 1 = D, 2 = D0, 3 = F, 4 = F0, 5 = B, 6 = B0, 7 = H, 8 = H0
-	        let crn_x = if lvl1x && eo.x || lvl3x.x && eo.y || lvl4x.x && dr.x || lvl6x.x && fo.y { 5 } else { if lvl1x || lvl3x.y && !eo.w || lvl4x.y && !bo.x || lvl6x.y && !ho.w { 1 } else { if lvl3x.x { 3 } else { if lvl3x.y { 7 } else { if lvl4x.x { 2 } else { if lvl4x.y { 6 } else { if lvl6x.x { 4 } else { if lvl6x.y { 8 } else { 0 }}}}}}}};
-	        let crn_y = if lvl1y && eo.y || lvl3y.x && eo.x || lvl4y.x && fo.y || lvl6y.x && dr.x { 5 } else { if lvl1y || lvl3y.y && !eo.z || lvl4y.y && !bo.y || lvl6y.y && !ho.z { 3 } else { if lvl3y.x { 1 } else { if lvl3y.y { 7 } else { if lvl4y.x { 4 } else { if lvl4y.y { 6 } else { if lvl6y.x { 2 } else { if lvl6y.y { 8 } else { 0 }}}}}}}};
-	        let crn_z = if lvl1z && eo.z || lvl3z.x && eo.w || lvl4z.x && fo.z || lvl6z.x && dr.w { 7 } else { if lvl1z || lvl3z.y && !eo.y || lvl4z.y && !ho.z || lvl6z.y && !bo.y { 3 } else { if lvl3z.x { 1 } else { if lvl3z.y { 5 } else { if lvl4z.x { 4 } else { if lvl4z.y { 8 } else { if lvl6z.x { 2 } else { if lvl6z.y { 6 } else { 0 }}}}}}}};
-	        let crn_w = if lvl1w && eo.w || lvl3w.x && eo.z || lvl4w.x && dr.w || lvl6w.x && fo.z { 7 } else { if lvl1w || lvl3w.y && !eo.x || lvl4w.y && !ho.w || lvl6w.y && !bo.x { 1 } else { if lvl3w.x { 3 } else { if lvl3w.y { 5 } else { if lvl4w.x { 2 } else { if lvl4w.y { 8 } else { if lvl6w.x { 4 } else { if lvl6w.y { 6 } else { 0 }}}}}}}};
-            let corners = U8Vec4 { x: crn_x, y: crn_y, z: crn_z, w: crn_w };
-
-            let mid_x = if lvl2x.x &&  eo.x || lvl2x.y &&  eo.y || lvl5x.x &&  dr.x || lvl5x.y &&  fo.y { 5 } else { if lvl2x.x { 1 } else { if lvl2x.y { 3 } else { if lvl5x.x { 2 } else { if lvl5x.y { 4 } else { if ec.x && dc.z && ec.y && fc.w { if  eo.x { if  eo.y { 5 } else { 3 }} else { 1 }} else {0}}}}}};
-            let mid_y = if lvl2y.x && !eo.y || lvl2y.y && !eo.z || lvl5y.x && !bo.y || lvl5y.y && !ho.z { 3 } else { if lvl2y.x { 5 } else { if lvl2y.y { 7 } else { if lvl5y.x { 6 } else { if lvl5y.y { 8 } else { if ec.y && bc.w && ec.z && hc.x { if !eo.y { if !eo.z { 3 } else { 7 }} else { 5 }} else {0}}}}}};
-            let mid_z = if lvl2z.x &&  eo.w || lvl2z.y &&  eo.z || lvl5z.x &&  dr.w || lvl5z.y &&  fo.z { 7 } else { if lvl2z.x { 1 } else { if lvl2z.y { 3 } else { if lvl5z.x { 2 } else { if lvl5z.y { 4 } else { if ec.z && fc.x && ec.w && dc.y { if  eo.z { if  eo.w { 7 } else { 1 }} else { 3 }} else {0}}}}}};
-            let mid_w = if lvl2w.x && !eo.x || lvl2w.y && !eo.w || lvl5w.x && !bo.x || lvl5w.y && !ho.w { 1 } else { if lvl2w.x { 5 } else { if lvl2w.y { 7 } else { if lvl5w.x { 6 } else { if lvl5w.y { 8 } else { if ec.w && hc.y && ec.x && bc.z { if !eo.w { if !eo.x { 1 } else { 5 }} else { 7 }} else {0}}}}}};
-            let mids = U8Vec4 { x: mid_x, y: mid_y, z: mid_z, w: mid_w };
-
-            pixels.push(PixelWithEdgeLevel {
-                pixel: e.pixel,
-                corners,
-                mids,
-            })
+            let n = EdgeNeighbourhood::new(image, x, y);
+
+            // Every level needs a res flag on E or one of its direct neighbours, so flat areas skip straight to all-E tags.
+            if (n.e.0 | n.d.0 | n.f.0 | n.b.0 | n.h.0) & 0xf == 0 {
+                pixels.push(PixelWithEdgeLevel::flat());
+                continue;
+            }
+
+            pixels.push(EdgeLevels::new(&n, options).tags(&n))
         }
-    }
+    });
     ImageWithEdgeLevels {
         width: image.width,
         height: image.height,
@@ -516,37 +800,181 @@ fn determine_edge_levels(image: &ImageWithCornerConfigurations) -> ImageWithEdge
     }
 }
 
+// The pixels pass 3 looks at around E, which reach three pixels out in a cross.
+#[derive(Debug, Clone, Copy)]
+struct EdgeNeighbourhood {
+    b1: PixelWithCornerConfiguration,
+    b0: PixelWithCornerConfiguration,
+    b: PixelWithCornerConfiguration,
+    d1: PixelWithCornerConfiguration,
+    d0: PixelWithCornerConfiguration,
+    d: PixelWithCornerConfiguration,
+    e: PixelWithCornerConfiguration,
+    f: PixelWithCornerConfiguration,
+    f0: PixelWithCornerConfiguration,
+    f1: PixelWithCornerConfiguration,
+    h: PixelWithCornerConfiguration,
+    h0: PixelWithCornerConfiguration,
+    h1: PixelWithCornerConfiguration,
+}
+impl EdgeNeighbourhood {
+    #[inline(always)]
+    fn new(image: &ImageWithCornerConfigurations, x: usize, y: usize) -> Self {
+        let offscreen = PixelWithCornerConfiguration::offscreen();
+        // Get the neighbouring pixels, returning transparent if they're out of bounds.
+        // Grid:
+        //         B1
+        //         B0
+        //         B
+        // D1 D0 D E F F0 F1  (E is the current pixel)
+        //         H
+        //         H0
+        //         H1
+        let index = y * image.width + x;
+        let b1 = if y<=2 { offscreen } else { image.pixels[index - image.width * 3] };
+        let b0 = if y<=1 { offscreen } else { image.pixels[index - image.width * 2] };
+        let b = if y==0 { offscreen } else { image.pixels[index - image.width] };
+        let d = if x==0 { offscreen } else { image.pixels[index - 1] };
+        let d0 = if x<=1 { offscreen } else { image.pixels[index - 2] };
+        let d1 = if x<=2 { offscreen } else { image.pixels[index - 3] };
+        let e = image.pixels[index];
+        let f = if x+1 >= image.width { offscreen } else { image.pixels[index + 1] };
+        let f0 = if x+2 >= image.width { offscreen } else { image.pixels[index + 2] };
+        let f1 = if x+3 >= image.width { offscreen } else { image.pixels[index + 3] };
+        let h = if y+1 >= image.height { offscreen } else { image.pixels[index + image.width] };
+        let h0 = if y+2 >= image.height { offscreen } else { image.pixels[index + image.width*2] };
+        let h1 = if y+3 >= image.height { offscreen } else { image.pixels[index + image.width*3] };
+        Self { b1, b0, b, d1, d0, d, e, f, f0, f1, h, h0, h1 }
+    }
+}
+
+// Which of pass 3's edge levels hold at a pixel, per corner (x = up left, y = up right, z = down right, w = down left).
+// Levels 2 and 5 are the mids between corners instead, and levels 2 to 6 hold two flags each, as per the comments below.
+#[derive(Debug, Clone, Copy)]
+struct EdgeLevels {
+    lvl1: BVec4,
+    lvl2: [BVec2; 4],
+    lvl3: [BVec2; 4],
+    lvl4: [BVec2; 4],
+    lvl5: [BVec2; 4],
+    lvl6: [BVec2; 4],
+}
+impl EdgeLevels {
+    #[inline(always)]
+    fn new(n: &EdgeNeighbourhood, options: &ScaleFxOptions) -> Self {
+        let EdgeNeighbourhood { b1, b0, b, d1, d0, d, e, f, f0, f1, h, h0, h1 } = *n;
+
+        // Extract data:
+        let ec = e.res(); let eh = e.horizontal_edges(); let ev = e.vertical_edges();
+        let dc = d.res(); let dh = d.horizontal_edges(); let d0c = d0.res(); let d0h = d0.horizontal_edges(); let d1h = d1.horizontal_edges();
+        let fc = f.res(); let fh = f.horizontal_edges(); let f0c = f0.res(); let f0h = f0.horizontal_edges(); let f1h = f1.horizontal_edges();
+        let bc = b.res(); let bv = b.vertical_edges(); let b0c = b0.res(); let b0v = b0.vertical_edges(); let b1v = b1.vertical_edges();
+        let hc = h.res(); let hv = h.vertical_edges(); let h0c = h0.res(); let h0v = h0.vertical_edges(); let h1v = h1.vertical_edges();
+
+        // Level 1 corners (horizontal, vertical):
+        let lvl1x = ec.x && (dc.z || bc.z || options.filter_corners);
+        let lvl1y = ec.y && (fc.w || bc.w || options.filter_corners);
+        let lvl1z = ec.z && (fc.x || hc.x || options.filter_corners);
+        let lvl1w = ec.w && (dc.y || hc.y || options.filter_corners);
+
+        // Level 2 mid (left, right / up, down):
+        let lvl2x = BVec2{ x: (ec.x && eh.y) && dc.z, y: (ec.y && eh.x) && fc.w };
+        let lvl2y = BVec2{ x: (ec.y && ev.z) && bc.w, y: (ec.z && ev.y) && hc.x };
+        let lvl2z = BVec2{ x: (ec.w && eh.z) && dc.y, y: (ec.z && eh.w) && fc.x };
+        let lvl2w = BVec2{ x: (ec.x && ev.w) && bc.z, y: (ec.w && ev.x) && hc.y };
+
+        // Level 3 corners (horizontal, vertical):
+        let lvl3x = BVec2{ x: lvl2x.y && (dh.y && dh.x) && fh.z, y: lvl2w.y && (bv.w && bv.x) && hv.z };
+        let lvl3y = BVec2{ x: lvl2x.x && (fh.x && fh.y) && dh.w, y: lvl2y.y && (bv.z && bv.y) && hv.w };
+        let lvl3z = BVec2{ x: lvl2z.x && (fh.w && fh.z) && dh.x, y: lvl2y.x && (hv.y && hv.z) && bv.x };
+        let lvl3w = BVec2{ x: lvl2z.y && (dh.z && dh.w) && fh.y, y: lvl2w.x && (hv.x && hv.w) && bv.y };
+
+        // Level 4 corners (horizontal, vertical):
+        let lvl4x = BVec2{ x: (dc.x && dh.y && eh.x && eh.y && fh.x && fh.y) && (d0c.z && d0h.w), y: (bc.x && bv.w && ev.x && ev.w && hv.x && hv.w) && (b0c.z && b0v.y) };
+        let lvl4y = BVec2{ x: (fc.y && fh.x && eh.y && eh.x && dh.y && dh.x) && (f0c.w && f0h.z), y: (bc.y && bv.z && ev.y && ev.z && hv.y && hv.z) && (b0c.w && b0v.x) };
+        let lvl4z = BVec2{ x: (fc.z && fh.w && eh.z && eh.w && dh.z && dh.w) && (f0c.x && f0h.y), y: (hc.z && hv.y && ev.z && ev.y && bv.z && bv.y) && (h0c.x && h0v.w) };
+        let lvl4w = BVec2{ x: (dc.w && dh.z && eh.w && eh.z && fh.w && fh.z) && (d0c.y && d0h.x), y: (hc.w && hv.x && ev.w && ev.x && bv.w && bv.x) && (h0c.y && h0v.z) };
+
+        // Level 5 mid (left, right / up, down):
+        let lvl5x = BVec2{ x: lvl4x.x && (f0h.x && f0h.y) && (d1h.z && d1h.w), y: lvl4y.x && (d0h.y && d0h.x) && (f1h.w && f1h.z) };
+        let lvl5y = BVec2{ x: lvl4y.y && (h0v.y && h0v.z) && (b1v.w && b1v.x), y: lvl4z.y && (b0v.z && b0v.y) && (h1v.x && h1v.w) };
+        let lvl5z = BVec2{ x: lvl4w.x && (f0h.w && f0h.z) && (d1h.y && d1h.x), y: lvl4z.x && (d0h.z && d0h.w) && (f1h.x && f1h.y) };
+        let lvl5w = BVec2{ x: lvl4x.y && (h0v.x && h0v.w) && (b1v.z && b1v.y), y: lvl4w.y && (b0v.w && b0v.x) && (h1v.y && h1v.z) };
+
+        // Level 6 corners (horizontal, vertical):
+        let lvl6x = BVec2{ x: lvl5x.y && (d1h.y && d1h.x), y: lvl5w.y && (b1v.w && b1v.x) };
+        let lvl6y = BVec2{ x: lvl5x.x && (f1h.x && f1h.y), y: lvl5y.y && (b1v.z && b1v.y) };
+        let lvl6z = BVec2{ x: lvl5z.x && (f1h.w && f1h.z), y: lvl5y.x && (h1v.y && h1v.z) };
+        let lvl6w = BVec2{ x: lvl5z.y && (d1h.z && d1h.w), y: lvl5w.x && (h1v.x && h1v.w) };
+
+        Self {
+            lvl1: BVec4 { x: lvl1x, y: lvl1y, z: lvl1z, w: lvl1w },
+            lvl2: [lvl2x, lvl2y, lvl2z, lvl2w],
+            lvl3: [lvl3x, lvl3y, lvl3z, lvl3w],
+            lvl4: [lvl4x, lvl4y, lvl4z, lvl4w],
+            lvl5: [lvl5x, lvl5y, lvl5z, lvl5w],
+            lvl6: [lvl6x, lvl6y, lvl6z, lvl6w],
+        }
+    }
+
+    // Picks each subpixel's tag from the levels.
+    #[inline(always)]
+    fn tags(&self, n: &EdgeNeighbourhood) -> PixelWithEdgeLevel {
+        let ec = n.e.res(); let eo = n.e.orientation();
+        let dc = n.d.res(); let dr = n.d.orientation();
+        let fc = n.f.res(); let fo = n.f.orientation();
+        let bc = n.b.res(); let bo = n.b.orientation();
+        let hc = n.h.res(); let ho = n.h.orientation();
+        let BVec4 { x: lvl1x, y: lvl1y, z: lvl1z, w: lvl1w } = self.lvl1;
+        let [lvl2x, lvl2y, lvl2z, lvl2w] = self.lvl2;
+        let [lvl3x, lvl3y, lvl3z, lvl3w] = self.lvl3;
+        let [lvl4x, lvl4y, lvl4z, lvl4w] = self.lvl4;
+        let [lvl5x, lvl5y, lvl5z, lvl5w] = self.lvl5;
+        let [lvl6x, lvl6y, lvl6z, lvl6w] = self.lvl6;
+
+        // Subpixels - 0 = E, 1 = D, 2 = D0, 3 = F, 4 = F0, 5 = B, 6 = B0, 7 = H, 8 = H0
+        let crn_x = if lvl1x && eo.x || lvl3x.x && eo.y || lvl4x.x && dr.x || lvl6x.x && fo.y { 5 } else { if lvl1x || lvl3x.y && !eo.w || lvl4x.y && !bo.x || lvl6x.y && !ho.w { 1 } else { if lvl3x.x { 3 } else { if lvl3x.y { 7 } else { if lvl4x.x { 2 } else { if lvl4x.y { 6 } else { if lvl6x.x { 4 } else { if lvl6x.y { 8 } else { 0 }}}}}}}};
+        let crn_y = if lvl1y && eo.y || lvl3y.x && eo.x || lvl4y.x && fo.y || lvl6y.x && dr.x { 5 } else { if lvl1y || lvl3y.y && !eo.z || lvl4y.y && !bo.y || lvl6y.y && !ho.z { 3 } else { if lvl3y.x { 1 } else { if lvl3y.y { 7 } else { if lvl4y.x { 4 } else { if lvl4y.y { 6 } else { if lvl6y.x { 2 } else { if lvl6y.y { 8 } else { 0 }}}}}}}};
+        let crn_z = if lvl1z && eo.z || lvl3z.x && eo.w || lvl4z.x && fo.z || lvl6z.x && dr.w { 7 } else { if lvl1z || lvl3z.y && !eo.y || lvl4z.y && !ho.z || lvl6z.y && !bo.y { 3 } else { if lvl3z.x { 1 } else { if lvl3z.y { 5 } else { if lvl4z.x { 4 } else { if lvl4z.y { 8 } else { if lvl6z.x { 2 } else { if lvl6z.y { 6 } else { 0 }}}}}}}};
+        let crn_w = if lvl1w && eo.w || lvl3w.x && eo.z || lvl4w.x && dr.w || lvl6w.x && fo.z { 7 } else { if lvl1w || lvl3w.y && !eo.x || lvl4w.y && !ho.w || lvl6w.y && !bo.x { 1 } else { if lvl3w.x { 3 } else { if lvl3w.y { 5 } else { if lvl4w.x { 2 } else { if lvl4w.y { 8 } else { if lvl6w.x { 4 } else { if lvl6w.y { 6 } else { 0 }}}}}}}};
+        let corners = U8Vec4 { x: crn_x, y: crn_y, z: crn_z, w: crn_w };
+
+        let mid_x = if lvl2x.x &&  eo.x || lvl2x.y &&  eo.y || lvl5x.x &&  dr.x || lvl5x.y &&  fo.y { 5 } else { if lvl2x.x { 1 } else { if lvl2x.y { 3 } else { if lvl5x.x { 2 } else { if lvl5x.y { 4 } else { if ec.x && dc.z && ec.y && fc.w { if  eo.x { if  eo.y { 5 } else { 3 }} else { 1 }} else {0}}}}}};
+        let mid_y = if lvl2y.x && !eo.y || lvl2y.y && !eo.z || lvl5y.x && !bo.y || lvl5y.y && !ho.z { 3 } else { if lvl2y.x { 5 } else { if lvl2y.y { 7 } else { if lvl5y.x { 6 } else { if lvl5y.y { 8 } else { if ec.y && bc.w && ec.z && hc.x { if !eo.y { if !eo.z { 3 } else { 7 }} else { 5 }} else {0}}}}}};
+        let mid_z = if lvl2z.x &&  eo.w || lvl2z.y &&  eo.z || lvl5z.x &&  dr.w || lvl5z.y &&  fo.z { 7 } else { if lvl2z.x { 1 } else { if lvl2z.y { 3 } else { if lvl5z.x { 2 } else { if lvl5z.y { 4 } else { if ec.z && fc.x && ec.w && dc.y { if  eo.z { if  eo.w { 7 } else { 1 }} else { 3 }} else {0}}}}}};
+        let mid_w = if lvl2w.x && !eo.x || lvl2w.y && !eo.w || lvl5w.x && !bo.x || lvl5w.y && !ho.w { 1 } else { if lvl2w.x { 5 } else { if lvl2w.y { 7 } else { if lvl5w.x { 6 } else { if lvl5w.y { 8 } else { if ec.w && hc.y && ec.x && bc.z { if !eo.w { if !eo.x { 1 } else { 5 }} else { 7 }} else {0}}}}}};
+        let mids = U8Vec4 { x: mid_x, y: mid_y, z: mid_z, w: mid_w };
+
+        PixelWithEdgeLevel::new(corners, mids)
+    }
+}
+
 // Outputs subpixels based on previously calculated tags.
 // This implements pass 4 from here:
 // https://github.com/libretro/slang-shaders/blob/master/edge-smoothing/scalefx/shaders/scalefx-pass4.slang
-fn scale_subpixels(image: &ImageWithEdgeLevels) -> Image {
-    let mut pixels: Vec<u32> = Vec::with_capacity(image.pixels.len() * 9);
-    let mut row0: Vec<u32> = Vec::with_capacity(image.width * 3);
-    let mut row1: Vec<u32> = Vec::with_capacity(image.width * 3);
-    let mut row2: Vec<u32> = Vec::with_capacity(image.width * 3);
-    for y in 0..image.height {
-        row0.clear();
-        row1.clear();
-        row2.clear();
-        for x in 0..image.width {
+// Only the given rows and columns of source pixels are output, which removes the border for free.
+fn scale_subpixels(colours: &Image, image: &ImageWithEdgeLevels, rows: Range<usize>, columns: Range<usize>, options: &ScaleFxOptions) -> Image {
+    let out_width = columns.len() * 3;
+    // Each source row outputs three rows of subpixels.
+    let pixels = map_rows(rows.len(), out_width * 3, options.thread_count(), |row, pixels: &mut Vec<u32>| {
+        let y = rows.start + row;
+        let row_start = pixels.len();
+        pixels.resize(row_start + out_width * 3, 0);
+        let out_rows = &mut pixels[row_start..];
+        for x in columns.clone() {
             let source = image.pixels[y * image.width + x];
-            let mid = source.mids;
-            let crn = source.corners;
+            if source.is_flat() {
+                let colour = colours.pixels[y * image.width + x];
+                let out_x = (x - columns.start) * 3;
+                for spy in 0..3 {
+                    out_rows[spy * out_width + out_x..spy * out_width + out_x + 3].fill(colour);
+                }
+                continue;
+            }
             for spy in 0..3 { // Loop the subpixels.
                 for spx in 0..3 {
                     // Figure out which tag to use for each subpixel:
-                    let sp: u8 = match (spx, spy) {
-                        (0, 0) => crn.x,
-                        (1, 0) => mid.x,
-                        (2, 0) => crn.y,
-                        (0, 1) => mid.w,
-                        (1, 1) => 0,
-                        (2, 1) => mid.y,
-                        (0, 2) => crn.w,
-                        (1, 2) => mid.z,
-                        (2, 2) => crn.z,
-                        _ => 0,                    
-                    };
+                    let sp = source.tag(spx, spy);
 
                     // Convert from a tag to an output coordinate:
                     // Tag 0 = coordinate E, 1 = D, 2 = D0, 3 = F, 4 = F0, 5 = B, 6 = B0, 7 = H, 8 = H0.
@@ -570,31 +998,50 @@ fn scale_subpixels(image: &ImageWithEdgeLevels) -> Image {
                     };
 
                     // Get the colour from that coordinate.
+                    let out_index = spy * out_width + (x - columns.start) * 3 + spx;
                     let x: isize = (x as isize) + offset_x;
                     let y: isize = (y as isize) + offset_y;
                     let in_bounds = 0<=x && x<(image.width as isize) && 0<=y && y<(image.height as isize); 
-                    let colour = if in_bounds { image.pixels[(y as usize) * image.width + (x as usize)].pixel } else { 0 };
-
-                    // Push it to the correct row.
-                    match spy {
-                        0 => row0.push(colour),
-                        1 => row1.push(colour),
-                        2 => row2.push(colour),
-                        _ => {},
-                    }
+                    let colour = if in_bounds { colours.pixels[(y as usize) * image.width + (x as usize)] } else { 0 };
+
+                    // Write it to the correct row.
+                    out_rows[out_index] = colour;
                 }
             }
         }
-        pixels.extend_from_slice(&row0);
-        pixels.extend_from_slice(&row1);
-        pixels.extend_from_slice(&row2);
-    }
+    });
     Image {
-        width: image.width * 3,
-        height: image.height * 3,
+        width: out_width,
+        height: rows.len() * 3,
         pixels,
     }
+}
 
+// Builds an image row by row, splitting the rows into bands across threads.
+// Each band is built in order and then concatenated, so the output is identical to the serial path.
+fn map_rows<T: Send>(height: usize, row_len: usize, threads: usize, row: impl Fn(usize, &mut Vec<T>) + Sync) -> Vec<T> {
+    let threads = threads.clamp(1, height.max(1));
+    if threads == 1 {
+        let mut pixels = Vec::with_capacity(height * row_len);
+        for y in 0..height { row(y, &mut pixels) }
+        return pixels;
+    }
+    let rows_per_band = height.div_ceil(threads);
+    let bands: Vec<Vec<T>> = std::thread::scope(|scope| {
+        let row = &row;
+        let handles: Vec<_> = (0..height).step_by(rows_per_band).map(|start| {
+            scope.spawn(move || {
+                let end = (start + rows_per_band).min(height);
+                let mut pixels = Vec::with_capacity((end - start) * row_len);
+                for y in start..end { row(y, &mut pixels) }
+                pixels
+            })
+        }).collect();
+        handles.into_iter().map(|handle| handle.join().expect("ScaleFX worker thread panicked")).collect()
+    });
+    let mut pixels = Vec::with_capacity(height * row_len);
+    for band in bands { pixels.extend(band) }
+    pixels
 }
 
 // Vector helpers:
@@ -626,8 +1073,14 @@ struct BVec4 {
     w: bool,
 }
 impl BVec4 {
-    fn zero() -> Self {
-        Self { x: false, y: false, z: false, w: false }
+    fn to_vec(self) -> Vec4 {
+        Vec4 { x: self.x as u8 as f32, y: self.y as u8 as f32, z: self.z as u8 as f32, w: self.w as u8 as f32 }
+    }
+    fn to_bits(self) -> u8 {
+        self.x as u8 | (self.y as u8) << 1 | (self.z as u8) << 2 | (self.w as u8) << 3
+    }
+    fn from_bits(bits: u8) -> Self {
+        Self { x: bits & 1 != 0, y: bits & 2 != 0, z: bits & 4 != 0, w: bits & 8 != 0 }
     }
 }
 
@@ -638,6 +1091,14 @@ struct U8Vec4 {
     z: u8,
     w: u8,
 }
+impl U8Vec4 { // Only for values that fit in a nibble, such as tags.
+    fn to_nibbles(self) -> u16 {
+        self.x as u16 | (self.y as u16) << 4 | (self.z as u16) << 8 | (self.w as u16) << 12
+    }
+    fn from_nibbles(nibbles: u16) -> Self {
+        Self { x: (nibbles & 0xf) as u8, y: (nibbles >> 4 & 0xf) as u8, z: (nibbles >> 8 & 0xf) as u8, w: (nibbles >> 12) as u8 }
+    }
+}
 
 #[derive(Debug, Copy, Clone)]
 struct Vec4 {
@@ -650,7 +1111,7 @@ impl Vec4 {
     fn zero() -> Self {
         Self { x: 0., y: 0., z: 0., w: 0. }
     }
-    fn to_bvec(&self) -> BVec4 {
+    fn to_bvec(self) -> BVec4 {
         BVec4 { x: self.x > 0.5, y: self.y > 0.5, z: self.z > 0.5, w: self.w > 0.5 }
     }
     fn step(edge: Self, x: Self) -> Self {
@@ -0,0 +1,165 @@
+//! Colour-coded images of each of ScaleFX's passes, for seeing why an image scaled the way it did.
+//! Every image is at 3x, lined up with the output, with each source pixel's 3x3 block showing that pixel's values.
+//! Subpixels with nothing to show are a faded copy of the source pixel, so the sprite can still be made out.
+
+use super::{
+    BVec4, Image, ImageWithCornerConfigurations, ImageWithCornerStrengths, ImageWithDistances, ImageWithEdgeLevels,
+    PixelWithCornerConfiguration, ScaleFxError, ScaleFxOptions, add_border, calculate_corner_strengths,
+    calculate_distances, determine_edge_levels, resolve_corner_configurations, validate_dimensions,
+};
+
+/// The intermediate results of one 3x scale, as images. See `dump_passes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassDump {
+    /// Pass 0: the colour distance to each of the pixel's eight neighbours, in the subpixel on that side, as a heatmap
+    /// from black (the same colour) through red and yellow to white (black vs white). The middle is the pixel itself.
+    pub distances: Image,
+    /// Pass 1: the strength of each corner's candidate, in that corner's subpixel, on the same heatmap.
+    /// Corners with no strength at all are left faded.
+    pub corner_strengths: Image,
+    /// Pass 2: the corners that were resolved to be smoothed (res in the shader), in red.
+    pub corners: Image,
+    /// Pass 2: the corners with a clear horizontal edge, where colours differ more up and down than side to side, in green.
+    pub horizontal_edges: Image,
+    /// Pass 2: the corners with a clear vertical edge, where colours differ more side to side than up and down, in blue.
+    pub vertical_edges: Image,
+    /// Pass 2: the corners oriented vertically (or in the shader), judged by the distances either side of the corner
+    /// without needing an edge to be clear, in orange.
+    pub orientation: Image,
+    /// Pass 3: where each subpixel is copied from (the tags). Faded if it's the pixel itself, otherwise red for the
+    /// pixel to the left, green for the right, blue for above and yellow for below, darker for two pixels away.
+    pub tags: Image,
+    /// Pass 4: the scaled image, as `Image::scale3x` returns it.
+    pub output: Image,
+}
+impl PassDump {
+    /// Every image along with a name for it, in pass order, eg for saving as "<name>.png".
+    pub fn images(&self) -> [(&'static str, &Image); 8] {
+        [
+            ("distances", &self.distances),
+            ("corner-strengths", &self.corner_strengths),
+            ("corners", &self.corners),
+            ("horizontal-edges", &self.horizontal_edges),
+            ("vertical-edges", &self.vertical_edges),
+            ("orientation", &self.orientation),
+            ("tags", &self.tags),
+            ("output", &self.output),
+        ]
+    }
+}
+
+/// Scales the image to 3x, keeping a colour-coded image of every pass along the way.
+/// This runs on the whole image at once, so the options' band rows are ignored, but is otherwise the same scale.
+pub fn dump_passes(image: &Image, options: &ScaleFxOptions) -> Result<PassDump, ScaleFxError> {
+    options.validate()?;
+    validate_dimensions(image.width, image.height, image.pixels.len(), 3)?;
+    let border = options.border_mode.border_size();
+    let bordered = add_border(image.width, image.height, &image.pixels, options.border_mode);
+    let distances = calculate_distances(&bordered, None, options);
+    let corners = calculate_corner_strengths(&distances, options);
+    let configurations = resolve_corner_configurations(&distances, &corners, options);
+    let edges = determine_edge_levels(&configurations, options);
+    let canvas = Canvas { bordered: &bordered, border, width: image.width, height: image.height };
+    Ok(PassDump {
+        distances: canvas.draw(|x, y, spx, spy| distance_colour(&distances, x, y, spx, spy)),
+        corner_strengths: canvas.draw(|x, y, spx, spy| corner_strength_colour(&corners, x, y, spx, spy)),
+        corners: canvas.draw(|x, y, spx, spy| flag_colour(&configurations, x, y, spx, spy, |pixel| pixel.res(), 0xe02020ff)),
+        horizontal_edges: canvas.draw(|x, y, spx, spy| flag_colour(&configurations, x, y, spx, spy, |pixel| pixel.horizontal_edges(), 0x20a020ff)),
+        vertical_edges: canvas.draw(|x, y, spx, spy| flag_colour(&configurations, x, y, spx, spy, |pixel| pixel.vertical_edges(), 0x2040e0ff)),
+        orientation: canvas.draw(|x, y, spx, spy| flag_colour(&configurations, x, y, spx, spy, |pixel| pixel.orientation(), 0xff8000ff)),
+        tags: canvas.draw(|x, y, spx, spy| tag_colour(&edges, x, y, spx, spy)),
+        output: image.scale3x(options)?,
+    })
+}
+
+// Draws 3x images of the bordered image's original area.
+struct Canvas<'a> {
+    bordered: &'a Image,
+    border: usize,
+    width: usize, // Of the original image.
+    height: usize,
+}
+impl Canvas<'_> {
+    // Calls the subpixel function with each bordered pixel position and subpixel, which returns the colour to draw,
+    // or None to draw the faded source pixel.
+    fn draw(&self, subpixel: impl Fn(usize, usize, usize, usize) -> Option<u32>) -> Image {
+        let out_width = self.width * 3;
+        let mut pixels = vec![0; out_width * self.height * 3];
+        for y in self.border..self.border + self.height {
+            for x in self.border..self.border + self.width {
+                let source = self.bordered.pixels[y * self.bordered.width + x];
+                for spy in 0..3 {
+                    for spx in 0..3 {
+                        let out_x = (x - self.border) * 3 + spx;
+                        let out_y = (y - self.border) * 3 + spy;
+                        pixels[out_y * out_width + out_x] = subpixel(x, y, spx, spy).unwrap_or_else(|| faded(source));
+                    }
+                }
+            }
+        }
+        Image::new(out_width, self.height * 3, pixels)
+    }
+}
+
+// Each neighbour's distance is stored by whichever of the pair it's up or right of, so look to the neighbour for the
+// left and lower sides.
+fn distance_colour(image: &ImageWithDistances, x: usize, y: usize, spx: usize, spy: usize) -> Option<u32> {
+    let at = |x: usize, y: usize| &image.pixels[y * image.width + x];
+    let distance = match (spx, spy) {
+        (0, 0) => at(x, y).colour_distance_up_left,
+        (1, 0) => at(x, y).colour_distance_up,
+        (2, 0) => at(x, y).colour_distance_up_right,
+        (0, 1) => at(x - 1, y).colour_distance_right,
+        (2, 1) => at(x, y).colour_distance_right,
+        (0, 2) => at(x - 1, y + 1).colour_distance_up_right,
+        (1, 2) => at(x, y + 1).colour_distance_up,
+        (2, 2) => at(x + 1, y + 1).colour_distance_up_left,
+        _ => return None,
+    };
+    Some(heat(distance))
+}
+
+fn corner_strength_colour(image: &ImageWithCornerStrengths, x: usize, y: usize, spx: usize, spy: usize) -> Option<u32> {
+    let pixel = &image.pixels[y * image.width + x];
+    let strength = match (spx, spy) {
+        (0, 0) => pixel.up_left,
+        (2, 0) => pixel.up_right,
+        (2, 2) => pixel.down_right,
+        (0, 2) => pixel.down_left,
+        _ => return None,
+    };
+    (strength > 0.).then(|| heat(strength))
+}
+
+fn flag_colour(image: &ImageWithCornerConfigurations, x: usize, y: usize, spx: usize, spy: usize, flags: impl Fn(PixelWithCornerConfiguration) -> BVec4, colour: u32) -> Option<u32> {
+    let flags = flags(image.pixels[y * image.width + x]);
+    let is_set = match (spx, spy) {
+        (0, 0) => flags.x,
+        (2, 0) => flags.y,
+        (2, 2) => flags.z,
+        (0, 2) => flags.w,
+        _ => false,
+    };
+    is_set.then_some(colour)
+}
+
+fn tag_colour(image: &ImageWithEdgeLevels, x: usize, y: usize, spx: usize, spy: usize) -> Option<u32> {
+    // Tag 0 = E, 1 = D, 2 = D0, 3 = F, 4 = F0, 5 = B, 6 = B0, 7 = H, 8 = H0, as per scale_subpixels.
+    const COLOURS: [u32; 8] = [0xff0000ff, 0x800000ff, 0x00c000ff, 0x006000ff, 0x0000ffff, 0x000080ff, 0xffd000ff, 0x806800ff];
+    let tag = image.pixels[y * image.width + x].tag(spx, spy);
+    COLOURS.get((tag as usize).checked_sub(1)?).copied()
+}
+
+// Black at 0, through red and yellow, to white at 1.
+fn heat(value: f32) -> u32 {
+    let value = value.clamp(0., 1.) * 3.;
+    let channel = |start: f32| ((value - start).clamp(0., 1.) * 255.).round() as u32;
+    channel(0.) << 24 | channel(1.) << 16 | channel(2.) << 8 | 0xff
+}
+
+// The colour, as if over white, a quarter of the way from white, so the overlays stand out against it.
+fn faded(colour: u32) -> u32 {
+    let alpha = colour & 0xff;
+    let channel = |shift: u32| 0xff - (0xff - ((colour >> shift) & 0xff)) * alpha / 0xff / 4;
+    channel(24) << 24 | channel(16) << 16 | channel(8) << 8 | 0xff
+}
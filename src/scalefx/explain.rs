@@ -0,0 +1,177 @@
+//! A report of every decision ScaleFX made for one source pixel, for diagnosing a bad spot in the output.
+//! The values come from the same passes as a real scale, read back at the pixel rather than drawn like `dump_passes`.
+
+use super::{
+    BVec2, BVec4, CornerStrengths, EdgeLevels, EdgeNeighbourhood, Image, ScaleFxError, ScaleFxOptions, Vec4,
+    add_border, calculate_corner_strengths, calculate_distances, determine_edge_levels, hybrid, junction_votes,
+    resolve_corner_configurations, scale_subpixels, validate_dimensions,
+};
+use std::fmt;
+
+/// Everything ScaleFX worked out for one source pixel. See `explain`.
+/// Anything per corner is in the order up left, up right, down right, down left (x, y, z, w in the shader).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    pub x: usize,
+    pub y: usize,
+    /// The colours in the 5x5 square around the pixel, row by row, with the pixel in the middle.
+    /// Pixels past the image's edge are as the border mode makes them, or transparent past the border.
+    pub neighbourhood: [[u32; 5]; 5],
+    /// Pass 0: the colour distance to the neighbours up left, up, up right and right, from 0 (the same) to 1.
+    /// The other four are stored by those neighbours.
+    pub distances: [f32; 4],
+    /// Pass 1: the strength of each corner's candidate.
+    pub corner_strengths: [f32; 4],
+    /// Pass 2: the dominance of the four corners meeting at each of the pixel's corner junctions.
+    /// Each junction's corners are of the pixels up left, up right, down right and down left of it.
+    pub junction_dominance: [[f32; 4]; 4],
+    /// Pass 2: the majority vote at each junction for which of its four corners win, in the same order.
+    pub junction_votes: [[bool; 4]; 4],
+    /// Pass 2: the corners resolved to be smoothed (res in the shader).
+    pub corners: [bool; 4],
+    /// Pass 2: the corners with a clear horizontal edge.
+    pub horizontal_edges: [bool; 4],
+    /// Pass 2: the corners with a clear vertical edge.
+    pub vertical_edges: [bool; 4],
+    /// Pass 2: the corners oriented vertically (or in the shader).
+    pub orientation: [bool; 4],
+    /// Pass 3: the edge level conditions that held, named as in the shader, eg "lvl1x" or "lvl4z.y".
+    pub levels: Vec<&'static str>,
+    /// Pass 3: where each subpixel is copied from, row by row: 0 = the pixel itself, 1 = the pixel to the left,
+    /// 2 = two to the left, 3 = right, 4 = two right, 5 = above, 6 = two above, 7 = below, 8 = two below.
+    pub tags: [[u8; 3]; 3],
+    /// Pass 4: the pixel's 3x3 block of the output, row by row, including the hybrid pass if it's enabled.
+    pub output: [[u32; 3]; 3],
+}
+
+/// Explains how ScaleFX scales the pixel at x, y to 3x, with every pass's values for it.
+/// Errors if the options or image are invalid, or the pixel is outside the image.
+pub fn explain(image: &Image, x: usize, y: usize, options: &ScaleFxOptions) -> Result<Explanation, ScaleFxError> {
+    options.validate()?;
+    validate_dimensions(image.width, image.height, image.pixels.len(), 3)?;
+    if x >= image.width || y >= image.height {
+        return Err(ScaleFxError::PixelOutOfBounds { x, y, width: image.width, height: image.height });
+    }
+    let border = options.border_mode.border_size();
+    let bordered = add_border(image.width, image.height, &image.pixels, options.border_mode);
+    let distances = calculate_distances(&bordered, None, options);
+    let corners = calculate_corner_strengths(&distances, options);
+    let configurations = resolve_corner_configurations(&distances, &corners, options);
+    let edges = determine_edge_levels(&configurations, options);
+    let (bx, by) = (x + border, y + border);
+    let rows = by..by + 1;
+    let columns = bx..bx + 1;
+    let scaled = scale_subpixels(&bordered, &edges, rows.clone(), columns.clone(), options);
+    let scaled = if options.hybrid { hybrid::reverse_anti_alias(&bordered, &edges, &scaled, rows, columns, options) } else { scaled };
+
+    let pixel = &distances.pixels[by * distances.width + bx];
+    let configuration = configurations.pixels[by * configurations.width + bx];
+    // The dominance and votes at the junction at the top left of the given bordered pixel, as per calculate_junctions.
+    let junction_at = |x: usize, y: usize| {
+        let at = |x: Option<usize>, y: Option<usize>| match (x, y) {
+            (Some(x), Some(y)) if x < corners.width && y < corners.height => corners.pixels[y * corners.width + x],
+            _ => CornerStrengths::offscreen(),
+        };
+        let junction = junction_votes(&at(x.checked_sub(1), y.checked_sub(1)), &at(Some(x), y.checked_sub(1)), &at(x.checked_sub(1), Some(y)), &at(Some(x), Some(y)));
+        (junction.0.to_array(), junction.1.to_bvec().to_array())
+    };
+    let junctions = [junction_at(bx, by), junction_at(bx + 1, by), junction_at(bx + 1, by + 1), junction_at(bx, by + 1)];
+    let neighbourhood = EdgeNeighbourhood::new(&configurations, bx, by);
+    let levels = EdgeLevels::new(&neighbourhood, options);
+    let tags = levels.tags(&neighbourhood);
+    let strengths = corners.pixels[by * corners.width + bx];
+
+    Ok(Explanation {
+        x,
+        y,
+        neighbourhood: std::array::from_fn(|row| std::array::from_fn(|column| {
+            let x = (bx + column).checked_sub(2).filter(|&x| x < bordered.width);
+            let y = (by + row).checked_sub(2).filter(|&y| y < bordered.height);
+            x.zip(y).map_or(0, |(x, y)| bordered.pixels[y * bordered.width + x])
+        })),
+        distances: [pixel.colour_distance_up_left, pixel.colour_distance_up, pixel.colour_distance_up_right, pixel.colour_distance_right],
+        corner_strengths: [strengths.up_left, strengths.up_right, strengths.down_right, strengths.down_left],
+        junction_dominance: junctions.map(|junction| junction.0),
+        junction_votes: junctions.map(|junction| junction.1),
+        corners: configuration.res().to_array(),
+        horizontal_edges: configuration.horizontal_edges().to_array(),
+        vertical_edges: configuration.vertical_edges().to_array(),
+        orientation: configuration.orientation().to_array(),
+        levels: levels.names(),
+        tags: std::array::from_fn(|spy| std::array::from_fn(|spx| tags.tag(spx, spy))),
+        output: std::array::from_fn(|spy| std::array::from_fn(|spx| scaled.pixels[spy * scaled.width + spx])),
+    })
+}
+
+impl EdgeLevels {
+    // The shader's names for the levels that hold.
+    fn names(&self) -> Vec<&'static str> {
+        const PAIRS: [[[&str; 2]; 4]; 5] = [
+            [["lvl2x.x", "lvl2x.y"], ["lvl2y.x", "lvl2y.y"], ["lvl2z.x", "lvl2z.y"], ["lvl2w.x", "lvl2w.y"]],
+            [["lvl3x.x", "lvl3x.y"], ["lvl3y.x", "lvl3y.y"], ["lvl3z.x", "lvl3z.y"], ["lvl3w.x", "lvl3w.y"]],
+            [["lvl4x.x", "lvl4x.y"], ["lvl4y.x", "lvl4y.y"], ["lvl4z.x", "lvl4z.y"], ["lvl4w.x", "lvl4w.y"]],
+            [["lvl5x.x", "lvl5x.y"], ["lvl5y.x", "lvl5y.y"], ["lvl5z.x", "lvl5z.y"], ["lvl5w.x", "lvl5w.y"]],
+            [["lvl6x.x", "lvl6x.y"], ["lvl6y.x", "lvl6y.y"], ["lvl6z.x", "lvl6z.y"], ["lvl6w.x", "lvl6w.y"]],
+        ];
+        let mut names: Vec<&'static str> = self.lvl1.to_array().iter().zip(["lvl1x", "lvl1y", "lvl1z", "lvl1w"])
+            .filter(|(is_set, _)| **is_set)
+            .map(|(_, name)| name)
+            .collect();
+        for (level, pairs) in [self.lvl2, self.lvl3, self.lvl4, self.lvl5, self.lvl6].iter().zip(PAIRS) {
+            for (BVec2 { x, y }, [x_name, y_name]) in level.iter().zip(pairs) {
+                if *x { names.push(x_name) }
+                if *y { names.push(y_name) }
+            }
+        }
+        names
+    }
+}
+
+impl BVec4 {
+    fn to_array(self) -> [bool; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+}
+
+impl Vec4 {
+    fn to_array(self) -> [f32; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const CORNERS: [&str; 4] = ["up left", "up right", "down right", "down left"];
+        const TAGS: [&str; 9] = ["itself", "left", "2 left", "right", "2 right", "up", "2 up", "down", "2 down"];
+        let flags = |flags: &[bool; 4]| {
+            let set: Vec<&str> = CORNERS.iter().zip(flags).filter(|(_, is_set)| **is_set).map(|(name, _)| *name).collect();
+            if set.is_empty() { "none".to_string() } else { set.join(", ") }
+        };
+
+        writeln!(f, "Pixel {},{}", self.x, self.y)?;
+        writeln!(f, "Neighbourhood (RRGGBBAA, the pixel in the middle):")?;
+        for row in &self.neighbourhood {
+            writeln!(f, "  {}", row.map(|colour| format!("{:08x}", colour)).join(" "))?;
+        }
+        let [up_left, up, up_right, right] = self.distances;
+        writeln!(f, "Distances: up left {:.3}, up {:.3}, up right {:.3}, right {:.3}", up_left, up, up_right, right)?;
+        writeln!(f, "Corner strengths: {}", CORNERS.iter().zip(self.corner_strengths).map(|(name, strength)| format!("{} {:.3}", name, strength)).collect::<Vec<_>>().join(", "))?;
+        writeln!(f, "Junctions (dominance of the corners up left, up right, down right, down left of it; vote):")?;
+        for ((name, dominance), votes) in CORNERS.iter().zip(self.junction_dominance).zip(self.junction_votes) {
+            let dominance = dominance.map(|value| format!("{:.3}", value)).join(" ");
+            let votes = votes.map(|vote| if vote { "1" } else { "0" }).join(" ");
+            writeln!(f, "  {:<10}  {}; {}", name, dominance, votes)?;
+        }
+        writeln!(f, "Corners: {}", flags(&self.corners))?;
+        writeln!(f, "Horizontal edges: {}", flags(&self.horizontal_edges))?;
+        writeln!(f, "Vertical edges: {}", flags(&self.vertical_edges))?;
+        writeln!(f, "Orientation: {}", flags(&self.orientation))?;
+        writeln!(f, "Levels: {}", if self.levels.is_empty() { "none".to_string() } else { self.levels.join(", ") })?;
+        writeln!(f, "Subpixels (tag: where from, output colour):")?;
+        for (tags, output) in self.tags.iter().zip(&self.output) {
+            let subpixels: Vec<String> = tags.iter().zip(output).map(|(&tag, colour)| format!("{} {:<7} {:08x}", tag, TAGS.get(tag as usize).unwrap_or(&"?"), colour)).collect();
+            writeln!(f, "  {}", subpixels.join(" | "))?;
+        }
+        Ok(())
+    }
+}
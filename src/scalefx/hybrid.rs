@@ -0,0 +1,97 @@
+//! The post pass of the scalefx-hybrid preset: Christoph Feck's reverse anti-aliasing, run over ScaleFX's output.
+//! https://github.com/libretro/slang-shaders/blob/master/edge-smoothing/scalefx/scalefx-hybrid.slangp
+//! https://github.com/libretro/slang-shaders/blob/master/anti-aliasing/shaders/reverse-aa.slang
+//! Where art is anti-aliased, a pixel's colour sits part way between its neighbours'. Reverse AA tilts each subpixel
+//! towards the neighbour on its side, steepening the ramp back towards the sharp edge it was made from.
+//! Flat areas and hard edges have nothing to tilt, so only ramps change.
+
+use super::{Image, ImageWithEdgeLevels, ScaleFxOptions, map_rows};
+use std::ops::Range;
+
+// REVERSEAA_SHARPNESS in the shader, at its default: how much steeper a ramp may be made than its existing steps.
+const SHARPNESS: f32 = 2.;
+
+// Where each subpixel's centre is across its pixel, from -1 at the left or top edge to 1 at the right or bottom.
+// Aka 2 * fp - 1 in the shader.
+const OFFSETS: [f32; 3] = [-2. / 3., 0., 2. / 3.];
+
+// Replaces every subpixel that ScaleFX took from its own pixel (tag 0) with that pixel reverse anti-aliased,
+// leaving the subpixels copied from neighbours to smooth the edges alone.
+// Takes the same bordered colours, rows and columns as scale_subpixels, along with its output.
+pub(super) fn reverse_anti_alias(colours: &Image, edges: &ImageWithEdgeLevels, scaled: &Image, rows: Range<usize>, columns: Range<usize>, options: &ScaleFxOptions) -> Image {
+    let out_width = scaled.width;
+    let pixels = map_rows(rows.len(), out_width * 3, options.thread_count(), |row, pixels: &mut Vec<u32>| {
+        let y = rows.start + row;
+        let row_start = pixels.len();
+        pixels.extend_from_slice(&scaled.pixels[row * out_width * 3..(row + 1) * out_width * 3]);
+        let out_rows = &mut pixels[row_start..];
+        for x in columns.clone() {
+            let Some(block) = reverse_anti_aliased(colours, x, y, options.alpha_cutoff) else { continue };
+            let source = edges.pixels[y * edges.width + x];
+            for (spy, block_row) in block.iter().enumerate() {
+                for (spx, &colour) in block_row.iter().enumerate() {
+                    if source.tag(spx, spy) == 0 {
+                        out_rows[spy * out_width + (x - columns.start) * 3 + spx] = colour;
+                    }
+                }
+            }
+        }
+    });
+    Image {
+        width: out_width,
+        height: scaled.height,
+        pixels,
+    }
+}
+
+// The pixel at x, y reverse anti-aliased into a 3x3 block of subpixels, keeping its alpha.
+// Returns None if it's transparent. Neighbours that are transparent or past the image's edge don't count,
+// so ramps never tilt towards transparency, and the sprite's outline is left as ScaleFX made it.
+fn reverse_anti_aliased(colours: &Image, x: usize, y: usize, alpha_cutoff: u8) -> Option<[[u32; 3]; 3]> {
+    let is_opaque = |colour: &u32| colour & 0xff >= alpha_cutoff as u32;
+    let e = Some(colours.pixels[y * colours.width + x]).filter(is_opaque)?;
+    let at = |dx: isize, dy: isize| -> Option<u32> {
+        let x = x.checked_add_signed(dx).filter(|&x| x < colours.width)?;
+        let y = y.checked_add_signed(dy).filter(|&y| y < colours.height)?;
+        Some(colours.pixels[y * colours.width + x]).filter(is_opaque)
+    };
+    // Grid:
+    //      B1
+    //      B
+    // D0 D E F F4
+    //      H
+    //      H5
+    let vertical = [at(0, -2), at(0, -1), at(0, 1), at(0, 2)];
+    let horizontal = [at(-2, 0), at(-1, 0), at(1, 0), at(2, 0)];
+
+    let mut block = [[e; 3]; 3];
+    for shift in [24, 16, 8] { // Red, green, blue.
+        let value = |colour: u32| ((colour >> shift) & 0xff) as f32 / 255.;
+        let [b1, b, h, h5] = vertical.map(|colour| colour.map(value));
+        let [d0, d, f, f4] = horizontal.map(|colour| colour.map(value));
+        let s = value(e);
+        let tilt_y = tilt(b1, b, s, h, h5);
+        for (spy, block_row) in block.iter_mut().enumerate() {
+            let s1 = s + OFFSETS[spy] * tilt_y;
+            let tilt_x = tilt(d0, d, s1, f, f4);
+            for (spx, subpixel) in block_row.iter_mut().enumerate() {
+                let s0 = s1 + OFFSETS[spx] * tilt_x;
+                let channel = (s0 * 255.).round().clamp(0., 255.) as u32;
+                *subpixel = (*subpixel & !(0xff << shift)) | channel << shift;
+            }
+        }
+    }
+    Some(block)
+}
+
+// One axis of the shader: how far s tilts from the middle of its pixel to the edge, given the values of the two
+// neighbours before it (n1, n2) and after it (n3, n4). Missing neighbours count as the same as the one nearer s.
+fn tilt(n1: Option<f32>, n2: Option<f32>, s: f32, n3: Option<f32>, n4: Option<f32>) -> f32 {
+    let (n1, n2) = match n2 { Some(n2) => (n1.unwrap_or(n2), n2), None => (s, s) };
+    let (n3, n4) = match n3 { Some(n3) => (n3, n4.unwrap_or(n3)), None => (s, s) };
+    let (aa, bb, cc, dd) = (n2 - n1, s - n2, n3 - s, n4 - n3);
+    let t = (7. * (bb + cc) - 3. * (aa + dd)) / 16.;
+    let m = if s < 0.5 { 2. * s } else { 2. * (1. - s) };
+    let m = m.min(SHARPNESS * bb.abs()).min(SHARPNESS * cc.abs());
+    t.max(-m).min(m) // GLSL's clamp, which unlike f32::clamp doesn't mind m going negative once s1 overshoots.
+}
@@ -0,0 +1,220 @@
+//! The ways pass 0 can measure how different two colours look.
+
+/// How the colour distance pass measures the difference between two colours.
+/// Every metric is scaled so black vs white is 1, as the threshold expects, and capped at 1.
+/// How alpha counts is up to the `AlphaMode`, which is the same for every metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ColourMetric {
+    /// Compuphase's "redmean" approximation in integer maths, which weights red, green and blue by how sensitive eyes
+    /// are to them: https://www.compuphase.com/cmetric.htm. The only metric with a SIMD version.
+    #[default]
+    Redmean,
+    /// The shader's own floating point version of redmean, which weights red and blue a little differently.
+    Shader,
+    /// The straight-line distance between the red, green and blue values.
+    Euclidean,
+    /// The straight-line distance in YCbCr (BT.601), with brightness weighted twice as heavily as each chroma channel.
+    YCbCr,
+    /// CIE76 ΔE: the straight-line distance in CIELAB, where black vs white is 100.
+    Lab76,
+    /// CIEDE2000 ΔE, which corrects CIELAB's unevenness in blues, greys and saturated colours.
+    Lab2000,
+    /// The straight-line distance in Oklab, a newer perceptual space that's more even than CIELAB.
+    Oklab,
+}
+impl ColourMetric {
+    /// Returns the distance between two 0xRRGGBBAA colours, from 0 for the same to 1 for black vs white.
+    /// Alpha is treated as per the default `AlphaMode::Binary`, with the default cutoff of 0x80.
+    pub fn distance(self, a: u32, b: u32) -> f32 {
+        self.distance_with_alpha(a, b, AlphaMode::Binary, 0x80)
+    }
+
+    /// As per `distance`, but with the given alpha mode. Pixels with an alpha below the cutoff count as fully transparent.
+    pub fn distance_with_alpha(self, a: u32, b: u32, alpha_mode: AlphaMode, alpha_cutoff: u8) -> f32 {
+        let a_is_transparent = a & 0xff < alpha_cutoff as u32;
+        let b_is_transparent = b & 0xff < alpha_cutoff as u32;
+        if a_is_transparent && b_is_transparent { return 0. } // Transparent vs transparent counts as the same.
+        match alpha_mode {
+            AlphaMode::Binary => {
+                if a_is_transparent || b_is_transparent { return 1. } // Colour -> transparent counts as different.
+                self.opaque_distance(a, b)
+            },
+            AlphaMode::Premultiplied => {
+                let a = if a_is_transparent { 0 } else { a };
+                let b = if b_is_transparent { 0 } else { b };
+                let colour = self.opaque_distance(premultiply(a), premultiply(b));
+                let alpha = (a & 0xff).abs_diff(b & 0xff) as f32 / 255.;
+                (colour * colour + alpha * alpha).sqrt().min(1.)
+            },
+        }
+    }
+
+    // The distance between two colours' red, green and blue, ignoring alpha.
+    fn opaque_distance(self, a: u32, b: u32) -> f32 {
+        if a >> 8 == b >> 8 { return 0. } // Save the complicated calculations below.
+        let distance = match self {
+            Self::Redmean => redmean(a, b),
+            Self::Shader => shader(rgb(a), rgb(b)),
+            Self::Euclidean => euclidean(rgb(a), rgb(b)) / 3f32.sqrt(),
+            Self::YCbCr => ycbcr(rgb(a), rgb(b)),
+            Self::Lab76 => euclidean(lab(a), lab(b)) / 100.,
+            Self::Lab2000 => ciede2000(lab(a), lab(b)) / 100.,
+            Self::Oklab => euclidean(oklab(a), oklab(b)),
+        };
+        distance.min(1.)
+    }
+}
+
+/// How alpha counts towards the distance between two colours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum AlphaMode {
+    /// As per the shader, pixels are either transparent or opaque, split at the alpha cutoff.
+    /// Two transparent pixels are the same, a transparent pixel is as different as can be from an opaque one,
+    /// and opaque pixels are compared ignoring their alpha.
+    #[default]
+    Binary,
+    /// Colours are compared premultiplied by their alpha, as they'd look over black, and the difference in alpha
+    /// counts too. So soft shadows and glows have edges like any other colour, and a faint pixel is close to
+    /// transparent while a solid one is far from it. Pixels below the alpha cutoff still count as fully transparent,
+    /// so lower it from the default to let faint pixels count.
+    Premultiplied,
+}
+
+// Scales red, green and blue by alpha, rounding to the nearest, and makes it opaque.
+fn premultiply(colour: u32) -> u32 {
+    let alpha = colour & 0xff;
+    let channel = |shift: u32| (((colour >> shift) & 0xff) * alpha + 0x7f) / 0xff;
+    (channel(24) << 24) | (channel(16) << 16) | (channel(8) << 8) | 0xff
+}
+
+// The metric this port has always used, returning 0 for same colours and 1 for white-black.
+fn redmean(a: u32, b: u32) -> f32 {
+    let a_r = a >> 24;
+    let a_g = (a >> 16) & 0xff;
+    let a_b = (a >> 8) & 0xff;
+
+    let b_r = b >> 24;
+    let b_g = (b >> 16) & 0xff;
+    let b_b = (b >> 8) & 0xff;
+
+    let r_mean = (a_r + b_r) / 2;
+    let r = a_r.abs_diff(b_r);
+    let g = a_g.abs_diff(b_g);
+    let b = a_b.abs_diff(b_b);
+
+    (((((512 + r_mean)*r*r)>>8) + 4*g*g + (((767-r_mean)*b*b)>>8)) as f32).sqrt() / 765.
+}
+
+// Red, green and blue from 0 to 1.
+fn rgb(colour: u32) -> [f32; 3] {
+    [(colour >> 24) as f32 / 255., ((colour >> 16) & 0xff) as f32 / 255., ((colour >> 8) & 0xff) as f32 / 255.]
+}
+
+// The shader's dist function:
+// https://github.com/libretro/slang-shaders/blob/master/edge-smoothing/scalefx/shaders/scalefx-pass0.slang
+fn shader(a: [f32; 3], b: [f32; 3]) -> f32 {
+    let r = 0.5 * (a[0] + b[0]);
+    let d = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    ((2. + r) * d[0] * d[0] + 4. * d[1] * d[1] + (3. - r) * d[2] * d[2]).sqrt() / 3.
+}
+
+fn euclidean(a: [f32; 3], b: [f32; 3]) -> f32 {
+    let d = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+}
+
+fn ycbcr(a: [f32; 3], b: [f32; 3]) -> f32 {
+    let d = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    let y = 0.299 * d[0] + 0.587 * d[1] + 0.114 * d[2];
+    let cb = -0.168736 * d[0] - 0.331264 * d[1] + 0.5 * d[2];
+    let cr = 0.5 * d[0] - 0.418688 * d[1] - 0.081312 * d[2];
+    (y * y + (cb * cb + cr * cr) / 4.).sqrt()
+}
+
+// Red, green and blue from 0 to 1, without sRGB's gamma, so they're proportional to the light given off.
+// The colour spaces below are converted to in f64, to keep their published constants' precision.
+fn linear_rgb(colour: u32) -> [f64; 3] {
+    rgb(colour).map(|c| {
+        let c = c as f64;
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    })
+}
+
+// CIELAB lightness and colour, relative to a D65 white point.
+// https://en.wikipedia.org/wiki/CIELAB_color_space#From_CIEXYZ_to_CIELAB
+fn lab(colour: u32) -> [f32; 3] {
+    let [r, g, b] = linear_rgb(colour);
+    let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+    let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+    let z = (0.0193339 * r + 0.1191920 * g + 0.9503041 * b) / 1.08883;
+    let f = |t: f64| if t > 216. / 24389. { t.cbrt() } else { t * 841. / 108. + 4. / 29. };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116. * fy - 16., 500. * (fx - fy), 200. * (fy - fz)].map(|v| v as f32)
+}
+
+// https://bottosson.github.io/posts/oklab/
+fn oklab(colour: u32) -> [f32; 3] {
+    let [r, g, b] = linear_rgb(colour);
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ].map(|v| v as f32)
+}
+
+// The CIEDE2000 colour difference between two CIELAB colours.
+// http://www2.ece.rochester.edu/~gsharma/ciede2000/ciede2000noteCRNA.pdf
+fn ciede2000([l1, a1, b1]: [f32; 3], [l2, a2, b2]: [f32; 3]) -> f32 {
+    let pow7 = |x: f32| x.powi(7);
+    let twenty_five_pow7 = pow7(25.);
+
+    // Stretch a* for greys, where CIELAB's hues bunch up.
+    let c_bar = ((a1 * a1 + b1 * b1).sqrt() + (a2 * a2 + b2 * b2).sqrt()) / 2.;
+    let g = 0.5 * (1. - (pow7(c_bar) / (pow7(c_bar) + twenty_five_pow7)).sqrt());
+    let a1 = a1 * (1. + g);
+    let a2 = a2 * (1. + g);
+    let c1 = (a1 * a1 + b1 * b1).sqrt();
+    let c2 = (a2 * a2 + b2 * b2).sqrt();
+    let hue = |a: f32, b: f32| if a == 0. && b == 0. { 0. } else { b.atan2(a).to_degrees().rem_euclid(360.) };
+    let h1 = hue(a1, b1);
+    let h2 = hue(a2, b2);
+
+    // The differences in lightness, chroma and hue.
+    let delta_l = l2 - l1;
+    let delta_c = c2 - c1;
+    let delta_h = match h2 - h1 {
+        _ if c1 * c2 == 0. => 0.,
+        delta if delta > 180. => delta - 360.,
+        delta if delta < -180. => delta + 360.,
+        delta => delta,
+    };
+    let delta_h = 2. * (c1 * c2).sqrt() * (delta_h / 2.).to_radians().sin();
+
+    // Weight them by where the colours are.
+    let l_bar = (l1 + l2) / 2.;
+    let c_bar = (c1 + c2) / 2.;
+    let h_bar = match h1 + h2 {
+        sum if c1 * c2 == 0. => sum,
+        sum if (h1 - h2).abs() <= 180. => sum / 2.,
+        sum if sum < 360. => (sum + 360.) / 2.,
+        sum => (sum - 360.) / 2.,
+    };
+    let cos = |degrees: f32| degrees.to_radians().cos();
+    let t = 1. - 0.17 * cos(h_bar - 30.) + 0.24 * cos(2. * h_bar) + 0.32 * cos(3. * h_bar + 6.) - 0.20 * cos(4. * h_bar - 63.);
+    let delta_theta = 30. * (-((h_bar - 275.) / 25.).powi(2)).exp();
+    let r_c = 2. * (pow7(c_bar) / (pow7(c_bar) + twenty_five_pow7)).sqrt();
+    let s_l = 1. + 0.015 * (l_bar - 50.).powi(2) / (20. + (l_bar - 50.).powi(2)).sqrt();
+    let s_c = 1. + 0.045 * c_bar;
+    let s_h = 1. + 0.015 * c_bar * t;
+    let r_t = -(2. * delta_theta).to_radians().sin() * r_c;
+
+    let l = delta_l / s_l;
+    let c = delta_c / s_c;
+    let h = delta_h / s_h;
+    (l * l + c * c + h * h + r_t * c * h).sqrt()
+}
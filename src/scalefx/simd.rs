@@ -0,0 +1,111 @@
+//! SIMD versions of the colour distance (pass 0) and corner strength (pass 1) maths.
+//! Each pixel's four distances, and its four corner strengths, are calculated at once in one vector.
+//! These produce bit-identical results to the scalar versions in the parent module.
+
+use super::{CornerStrengths, Image, ImageWithDistances, PixelWithDistances, ScaleFxOptions};
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+// True if the CPU can run the functions below.
+pub(super) fn is_supported() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    return std::is_x86_feature_detected!("sse4.1");
+    #[cfg(not(target_arch = "x86_64"))]
+    return false;
+}
+
+// Calculates one row of pass 0.
+// Safety: is_supported must have returned true.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.1")]
+pub(super) unsafe fn calculate_distances_row(image: &Image, y: usize, pixels: &mut Vec<PixelWithDistances>, alpha_cutoff: u8) {
+    super::calculate_distances_row(image, y, pixels, |center, others| colour_distances(center, others, alpha_cutoff));
+}
+
+// Calculates one row of pass 1.
+// Safety: is_supported must have returned true.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.1")]
+pub(super) unsafe fn calculate_corner_strengths_row(image: &ImageWithDistances, y: usize, pixels: &mut Vec<CornerStrengths>, options: &ScaleFxOptions) {
+    super::calculate_corner_strengths_row(image, y, pixels, |d, a_x, a_y, b_x, b_y| corner_strengths(options, d, a_x, a_y, b_x, b_y));
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub(super) unsafe fn calculate_distances_row(_image: &Image, _y: usize, _pixels: &mut Vec<PixelWithDistances>, _alpha_cutoff: u8) {
+    unreachable!("SIMD isn't supported on this architecture")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub(super) unsafe fn calculate_corner_strengths_row(_image: &ImageWithDistances, _y: usize, _pixels: &mut Vec<CornerStrengths>, _options: &ScaleFxOptions) {
+    unreachable!("SIMD isn't supported on this architecture")
+}
+
+// ColourMetric::Redmean with AlphaMode::Binary from one pixel to four others.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.1")]
+fn colour_distances(a: u32, b: [u32; 4], alpha_cutoff: u8) -> [f32; 4] {
+    let a = _mm_set1_epi32(a as i32);
+    let b = _mm_setr_epi32(b[0] as i32, b[1] as i32, b[2] as i32, b[3] as i32);
+    let byte = _mm_set1_epi32(0xff);
+
+    let a_r = _mm_srli_epi32::<24>(a);
+    let a_g = _mm_and_si128(_mm_srli_epi32::<16>(a), byte);
+    let a_b = _mm_and_si128(_mm_srli_epi32::<8>(a), byte);
+    let a_a = _mm_and_si128(a, byte);
+
+    let b_r = _mm_srli_epi32::<24>(b);
+    let b_g = _mm_and_si128(_mm_srli_epi32::<16>(b), byte);
+    let b_b = _mm_and_si128(_mm_srli_epi32::<8>(b), byte);
+    let b_a = _mm_and_si128(b, byte);
+
+    // Transparent vs transparent counts as the same, colour -> transparent counts as different.
+    let cutoff = _mm_set1_epi32(alpha_cutoff as i32);
+    let is_a_transparent = _mm_cmplt_epi32(a_a, cutoff);
+    let is_b_transparent = _mm_cmplt_epi32(b_a, cutoff);
+    let are_both_transparent = _mm_castsi128_ps(_mm_and_si128(is_a_transparent, is_b_transparent));
+    let is_either_transparent = _mm_castsi128_ps(_mm_or_si128(is_a_transparent, is_b_transparent));
+
+    // Every channel is at most 0xff, so none of this overflows an i32.
+    let r_mean = _mm_srli_epi32::<1>(_mm_add_epi32(a_r, b_r));
+    let r = _mm_abs_epi32(_mm_sub_epi32(a_r, b_r));
+    let g = _mm_abs_epi32(_mm_sub_epi32(a_g, b_g));
+    let b = _mm_abs_epi32(_mm_sub_epi32(a_b, b_b));
+    let red = _mm_srli_epi32::<8>(_mm_mullo_epi32(_mm_mullo_epi32(_mm_add_epi32(_mm_set1_epi32(512), r_mean), r), r));
+    let green = _mm_slli_epi32::<2>(_mm_mullo_epi32(g, g));
+    let blue = _mm_srli_epi32::<8>(_mm_mullo_epi32(_mm_mullo_epi32(_mm_sub_epi32(_mm_set1_epi32(767), r_mean), b), b));
+    let sum = _mm_add_epi32(_mm_add_epi32(red, green), blue);
+
+    let distance = _mm_div_ps(_mm_sqrt_ps(_mm_cvtepi32_ps(sum)), _mm_set1_ps(765.));
+    let distance = _mm_blendv_ps(distance, _mm_set1_ps(1.), is_either_transparent);
+    let distance = _mm_andnot_ps(are_both_transparent, distance);
+    // Safety: __m128 and [f32; 4] have the same size, and every bit pattern is a valid f32.
+    unsafe { std::mem::transmute::<__m128, [f32; 4]>(distance) }
+}
+
+// corner_strength for all four corners at once.
+// The min/max operand order matters: with equal operands SSE returns the second one, which matches f32::clamp's handling of -0.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.1")]
+fn corner_strengths(options: &ScaleFxOptions, d: [f32; 4], a_x: [f32; 4], a_y: [f32; 4], b_x: [f32; 4], b_y: [f32; 4]) -> [f32; 4] {
+    let load = |v: [f32; 4]| _mm_setr_ps(v[0], v[1], v[2], v[3]);
+    let (d, a_x, a_y, b_x, b_y) = (load(d), load(a_x), load(a_y), load(b_x), load(b_y));
+    let zero = _mm_setzero_ps();
+    let one = _mm_set1_ps(1.);
+    let threshold = _mm_set1_ps(options.threshold);
+
+    let diff = _mm_sub_ps(a_x, a_y);
+    let weight_1 = _mm_div_ps(_mm_max_ps(_mm_sub_ps(threshold, d), zero), threshold);
+    let is_x_g_y = _mm_cmpgt_ps(_mm_add_ps(_mm_min_ps(a_x, b_x), a_x), _mm_add_ps(_mm_min_ps(a_y, b_y), a_y));
+    let x_g_y_diff = _mm_blendv_ps(_mm_xor_ps(diff, _mm_set1_ps(-0.)), diff, is_x_g_y);
+    let weight_2_raw = _mm_add_ps(_mm_sub_ps(one, d), x_g_y_diff);
+    let weight_2 = _mm_min_ps(one, _mm_max_ps(zero, weight_2_raw));
+    let strength = _mm_mul_ps(_mm_mul_ps(_mm_mul_ps(weight_1, weight_2), a_x), a_y);
+    let strength = if options.is_filter_aa_enabled {
+        strength
+    } else {
+        _mm_and_ps(strength, _mm_cmplt_ps(_mm_mul_ps(_mm_set1_ps(2.), d), _mm_add_ps(a_x, a_y)))
+    };
+    // Safety: __m128 and [f32; 4] have the same size, and every bit pattern is a valid f32.
+    unsafe { std::mem::transmute::<__m128, [f32; 4]>(strength) }
+}
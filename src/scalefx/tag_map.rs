@@ -0,0 +1,171 @@
+//! Saving the shape of a scale and applying it to another image.
+//!
+//! Pass 3's tags say which neighbour each subpixel is copied from, so they decide the output's shape on their own,
+//! whatever the colours. A `TagMap` keeps them, so a palette variant of a sprite can be scaled with exactly the shape
+//! the original got, rather than a slightly different one from its different colour distances.
+//! Several images can share tags too, made from all of them at once: see `scale_group`.
+//! The file format is the magic "SFXTAGS1", then the width, height and number of 3x steps (u32s, little endian),
+//! then every step's tags: a u32 per pixel (little endian, row by row), each step being 3x the size of the last.
+
+use super::{
+    Image, ImageWithEdgeLevels, PixelWithEdgeLevel, ScaleFxError, ScaleFxOptions, add_border,
+    calculate_corner_strengths, calculate_distances, determine_edge_levels, hybrid, resolve_corner_configurations,
+    scale_subpixels, validate_dimensions,
+};
+use crate::factor;
+
+const MAGIC: &[u8; 8] = b"SFXTAGS1";
+
+/// The tags from scaling an image, for applying to others of the same size. See the module docs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagMap {
+    /// The size of the images it applies to.
+    pub width: usize,
+    pub height: usize,
+    // The tags of each 3x step's own pixels, without the border.
+    steps: Vec<Vec<PixelWithEdgeLevel>>,
+}
+impl TagMap {
+    /// Scales the image by the factor, keeping the tags of every 3x step on the way.
+    pub fn new(image: &Image, factor: usize, options: &ScaleFxOptions) -> Result<Self, ScaleFxError> {
+        Self::for_group(std::slice::from_ref(image), factor, options)
+    }
+
+    /// Works out one set of tags for several images of the same size, such as a sprite's palette variants, from the
+    /// largest of their colour distances. That smooths an edge wherever any of the images has one, so the tags suit
+    /// them all, and applying them gives each the same shape. See `scale_group`.
+    pub fn for_group(images: &[Image], factor: usize, options: &ScaleFxOptions) -> Result<Self, ScaleFxError> {
+        let native = factor::validate_factor(factor)?;
+        options.validate()?;
+        let first = images.first().ok_or(ScaleFxError::EmptyGroup)?;
+        for image in images {
+            validate_dimensions(image.width, image.height, image.pixels.len(), native)?;
+            if (image.width, image.height) != (first.width, first.height) {
+                return Err(ScaleFxError::GroupSizeMismatch { width: first.width, height: first.height, other_width: image.width, other_height: image.height });
+            }
+        }
+        let mut steps = Vec::new();
+        let mut scaled = images.to_vec();
+        for _ in 0..native.ilog(3) {
+            let tags = step_tags(&scaled, options);
+            scaled = scaled.iter().map(|image| apply_step(image, &tags, options)).collect();
+            steps.push(tags);
+        }
+        Ok(Self { width: first.width, height: first.height, steps })
+    }
+
+    /// The factor the tags were made for, which is always a power of 3; others were shrunk from it.
+    pub fn native_factor(&self) -> usize {
+        3usize.pow(self.steps.len() as u32)
+    }
+
+    /// Scales the image by the factor with these tags, instead of working out its own.
+    /// The image must be the same size as the one the tags were made from, and the factor must shrink from the same
+    /// native factor. The threshold and colour metric don't matter, as the tags have already decided the shape, but
+    /// the border and hybrid modes still apply.
+    pub fn apply(&self, image: &Image, factor: usize, options: &ScaleFxOptions) -> Result<Image, ScaleFxError> {
+        let native = factor::validate_factor(factor)?;
+        options.validate()?;
+        validate_dimensions(image.width, image.height, image.pixels.len(), self.native_factor())?;
+        if (image.width, image.height) != (self.width, self.height) {
+            return Err(ScaleFxError::TagMapMismatch(format!("it's for {} x {} images, but the image is {} x {}", self.width, self.height, image.width, image.height)));
+        }
+        if native != self.native_factor() {
+            return Err(ScaleFxError::TagMapMismatch(format!("it's for scaling to {}x, which can't make {}x", self.native_factor(), factor)));
+        }
+        let mut scaled = image.clone();
+        for tags in &self.steps {
+            scaled = apply_step(&scaled, tags, options);
+        }
+        let (width, height, pixels) = factor::shrink(scaled.width, scaled.height, &scaled.pixels, self.native_factor(), factor);
+        Ok(Image { width, height, pixels })
+    }
+
+    /// Encodes the tags in the format in the module docs.
+    /// Errors if the image is too big for the format's 32 bit dimensions.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ScaleFxError> {
+        let mut bytes = MAGIC.to_vec();
+        for value in [self.width, self.height, self.steps.len()] {
+            let value = u32::try_from(value).map_err(|_| ScaleFxError::Encode(format!("{} x {} is too big for a tag map", self.width, self.height)))?;
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for tags in self.steps.iter().flatten() {
+            bytes.extend_from_slice(&tags.0.to_le_bytes());
+        }
+        Ok(bytes)
+    }
+
+    /// Decodes tags in the format in the module docs.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ScaleFxError> {
+        let invalid = |reason: &str| ScaleFxError::Decode(format!("Invalid tag map: {}", reason));
+        let header = bytes.strip_prefix(MAGIC).ok_or_else(|| invalid("it doesn't start with SFXTAGS1"))?;
+        let mut words = header.chunks_exact(4).map(|word| u32::from_le_bytes(word.try_into().unwrap()));
+        let (Some(width), Some(height), Some(step_count)) = (words.next(), words.next(), words.next()) else {
+            return Err(invalid("the header is cut short"));
+        };
+        let (width, height) = (width as usize, height as usize);
+        if step_count > 20 { return Err(invalid("it has too many steps")) }
+        validate_dimensions(width, height, width * height, 3usize.pow(step_count)).map_err(|error| invalid(&error.to_string()))?;
+        let mut steps = Vec::new();
+        for step in 0..step_count {
+            let size = 3usize.pow(step);
+            let tags: Vec<PixelWithEdgeLevel> = words.by_ref().take(width * size * height * size).map(PixelWithEdgeLevel).collect();
+            if tags.len() != width * size * height * size { return Err(invalid("the tags are cut short")) }
+            if tags.iter().any(|&tags| !tags.is_valid()) { return Err(invalid("a tag is out of range")) }
+            steps.push(tags);
+        }
+        if header.len() != (3 + steps.iter().map(Vec::len).sum::<usize>()) * 4 { return Err(invalid("there's data after the tags")) }
+        Ok(Self { width, height, steps })
+    }
+
+    /// Saves the tags to a file, in the format in the module docs.
+    pub fn save(&self, path: &str) -> Result<(), ScaleFxError> {
+        Ok(std::fs::write(path, self.to_bytes()?)?)
+    }
+
+    /// Loads tags saved by `save`.
+    pub fn load(path: &str) -> Result<Self, ScaleFxError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+// Works out the tags of one 3x step shared by the same-sized images, without the border.
+fn step_tags(images: &[Image], options: &ScaleFxOptions) -> Vec<PixelWithEdgeLevel> {
+    let border = options.border_mode.border_size();
+    let image = &images[0];
+    let distance_images = images.iter().map(|image| calculate_distances(&add_border(image.width, image.height, &image.pixels, options.border_mode), None, options));
+    let distances = distance_images.reduce(|mut distances, other| {
+        for (distance, other) in distances.pixels.iter_mut().zip(&other.pixels) {
+            *distance = distance.max(other);
+        }
+        distances
+    }).expect("Groups have at least one image");
+    let corners = calculate_corner_strengths(&distances, options);
+    let configurations = resolve_corner_configurations(&distances, &corners, options);
+    let edges = determine_edge_levels(&configurations, options);
+    (border..border + image.height).flat_map(|y| &edges.pixels[y * edges.width + border..y * edges.width + border + image.width]).copied().collect()
+}
+
+// Scales the image to 3x with the given tags for its pixels, as per the last pass (and the hybrid pass, if enabled).
+fn apply_step(image: &Image, tags: &[PixelWithEdgeLevel], options: &ScaleFxOptions) -> Image {
+    let border = options.border_mode.border_size();
+    let bordered = add_border(image.width, image.height, &image.pixels, options.border_mode);
+    // The border's own tags are never output, so they can be anything.
+    let mut edges = ImageWithEdgeLevels { width: bordered.width, height: bordered.height, pixels: vec![PixelWithEdgeLevel::flat(); bordered.width * bordered.height] };
+    for (y, row) in tags.chunks_exact(image.width).enumerate() {
+        let start = (y + border) * edges.width + border;
+        edges.pixels[start..start + image.width].copy_from_slice(row);
+    }
+    let rows = border..border + image.height;
+    let columns = border..border + image.width;
+    let scaled = scale_subpixels(&bordered, &edges, rows.clone(), columns.clone(), options);
+    if options.hybrid { hybrid::reverse_anti_alias(&bordered, &edges, &scaled, rows, columns, options) } else { scaled }
+}
+
+/// Scales several images of the same size, such as a sprite's palette variants, with one shared set of tags, so
+/// they all get exactly the same shape. The tags come from the largest of the images' colour distances (see
+/// `TagMap::for_group`), so each is smoothed wherever any of them would be.
+pub fn scale_group(images: &[Image], factor: usize, options: &ScaleFxOptions) -> Result<Vec<Image>, ScaleFxError> {
+    let tags = TagMap::for_group(images, factor, options)?;
+    images.iter().map(|image| tags.apply(image, factor, options)).collect()
+}
@@ -0,0 +1,161 @@
+//! Sprite sheet scaling, where each cell is scaled on its own so neighbouring frames can't bleed into each other.
+
+use crate::error::ScaleFxError;
+use crate::factor;
+use crate::scalefx::{self, Image, ScaleFxOptions};
+
+/// The grid of cells in a sprite sheet, in pixels.
+/// The first cell is `margin` pixels in from the sheet's top and left, and there are `spacing` pixels between cells.
+/// Only whole cells are used; any pixels outside them (the margin, spacing, and leftovers along the right and bottom)
+/// are simply enlarged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SheetLayout {
+    pub cell_width: usize,
+    pub cell_height: usize,
+    pub spacing: usize,
+    pub margin: usize,
+}
+impl SheetLayout {
+    /// A grid of cells of the given size, with no spacing or margin.
+    pub fn new(cell_width: usize, cell_height: usize) -> Self {
+        Self { cell_width, cell_height, spacing: 0, margin: 0 }
+    }
+
+    /// Returns a copy with the given spacing between cells.
+    pub fn with_spacing(self, spacing: usize) -> Self {
+        Self { spacing, ..self }
+    }
+
+    /// Returns a copy with the given margin around the top and left of the grid.
+    pub fn with_margin(self, margin: usize) -> Self {
+        Self { margin, ..self }
+    }
+
+    /// Returns the layout of the sheet once it's been scaled by the given factor, or an error if it overflows.
+    pub fn scaled(self, scale: usize) -> Result<Self, ScaleFxError> {
+        let scale_value = |value: usize| value.checked_mul(scale).ok_or_else(|| ScaleFxError::InvalidOptions(format!("the sheet layout is too large to scale by {}", scale)));
+        Ok(Self {
+            cell_width: scale_value(self.cell_width)?,
+            cell_height: scale_value(self.cell_height)?,
+            spacing: scale_value(self.spacing)?,
+            margin: scale_value(self.margin)?,
+        })
+    }
+
+    /// Returns an error if the cells are empty, or the cells, spacing and margin are too large to add up.
+    pub fn validate(&self) -> Result<(), ScaleFxError> {
+        if self.cell_width == 0 || self.cell_height == 0 {
+            return Err(ScaleFxError::InvalidOptions(format!("sheet cells must not be empty, but were {}x{}", self.cell_width, self.cell_height)));
+        }
+        let fits = |cell: usize| cell.checked_add(self.spacing).is_some() && cell.checked_add(self.margin).is_some();
+        if !fits(self.cell_width) || !fits(self.cell_height) {
+            return Err(ScaleFxError::InvalidOptions(format!("sheet cells of {}x{} with a spacing of {} and a margin of {} are too large", self.cell_width, self.cell_height, self.spacing, self.margin)));
+        }
+        Ok(())
+    }
+
+    // The top-left corner of every whole cell that fits in a sheet of the given size, row by row.
+    // The layout must be valid, so a cell plus the spacing can't overflow, but the starts can as they step past the
+    // end of the sheet.
+    fn cells(&self, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let starts = |len: usize, cell: usize| {
+            std::iter::successors(Some(self.margin), move |start| start.checked_add(cell + self.spacing))
+                .take_while(move |start| start.checked_add(cell).is_some_and(|end| end <= len))
+        };
+        starts(height, self.cell_height)
+            .flat_map(|y| starts(width, self.cell_width).map(move |x| (x, y)))
+            .collect()
+    }
+}
+
+/// Scales a sprite sheet to 3x, scaling each cell as if it were alone, surrounded by the options' border mode.
+/// The scaled sheet has the same layout, scaled by 3.
+pub fn scale3x_sheet(sheet: &Image, layout: &SheetLayout, options: &ScaleFxOptions) -> Result<Image, ScaleFxError> {
+    scale_cells(sheet, layout, options, 3, Image::scale3x)
+}
+
+/// As per `scale3x_sheet`, but to 9x. Each cell is kept isolated through both of the 3x scales.
+pub fn scale9x_sheet(sheet: &Image, layout: &SheetLayout, options: &ScaleFxOptions) -> Result<Image, ScaleFxError> {
+    scale_cells(sheet, layout, options, 9, Image::scale9x)
+}
+
+/// As per `scale3x_sheet`, but by any whole number factor. Factors that aren't powers of 3 are shrunk from the next
+/// power of 3 up, as per `factor`, which keeps the cells lined up since every source pixel still becomes a whole block.
+pub fn scale_sheet(sheet: &Image, layout: &SheetLayout, factor: usize, options: &ScaleFxOptions) -> Result<Image, ScaleFxError> {
+    let native = factor::validate_factor(factor)?;
+    options.validate()?;
+    layout.validate()?;
+    scalefx::validate_dimensions(sheet.width, sheet.height, sheet.pixels.len(), native)?;
+    let mut scaled = sheet.clone();
+    let mut scale = 1;
+    while scale < native {
+        scaled = scale3x_sheet(&scaled, &layout.scaled(scale)?, options)?;
+        scale *= 3;
+    }
+    let (width, height, pixels) = factor::shrink(scaled.width, scaled.height, &scaled.pixels, native, factor);
+    Ok(Image::new(width, height, pixels))
+}
+
+fn scale_cells(sheet: &Image, layout: &SheetLayout, options: &ScaleFxOptions, scale: usize, scale_cell: impl Fn(&Image, &ScaleFxOptions) -> Result<Image, ScaleFxError> + Sync) -> Result<Image, ScaleFxError> {
+    options.validate()?;
+    layout.validate()?;
+    scalefx::validate_dimensions(sheet.width, sheet.height, sheet.pixels.len(), scale)?;
+
+    // Cells are usually small, so it's quicker to spread the cells across threads than each cell's rows.
+    let cells = layout.cells(sheet.width, sheet.height);
+    let threads = options.thread_count().clamp(1, cells.len().max(1));
+    let cell_options = options.with_threads(1);
+    let scale_cells = |cells: &[(usize, usize)]| -> Result<Vec<Image>, ScaleFxError> {
+        cells.iter().map(|&(x, y)| scale_cell(&crop(sheet, x, y, layout.cell_width, layout.cell_height), &cell_options)).collect()
+    };
+    let scaled_cells: Vec<Image> = if threads == 1 {
+        scale_cells(&cells)?
+    } else {
+        let cells_per_thread = cells.len().div_ceil(threads);
+        let batches: Vec<Result<Vec<Image>, ScaleFxError>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = cells.chunks(cells_per_thread).map(|cells| scope.spawn(|| scale_cells(cells))).collect();
+            handles.into_iter().map(|handle| handle.join().expect("ScaleFX worker thread panicked")).collect()
+        });
+        let mut scaled_cells = Vec::with_capacity(cells.len());
+        for batch in batches { scaled_cells.extend(batch?) }
+        scaled_cells
+    };
+
+    // Everything outside the cells is just enlarged, then the scaled cells go on top.
+    let mut out = enlarge(sheet, scale);
+    for (&(x, y), cell) in cells.iter().zip(&scaled_cells) {
+        paste(&mut out, cell, x * scale, y * scale);
+    }
+    Ok(out)
+}
+
+// Copies part of an image out.
+fn crop(image: &Image, x: usize, y: usize, width: usize, height: usize) -> Image {
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in image.pixels.chunks_exact(image.width).skip(y).take(height) {
+        pixels.extend_from_slice(&row[x..x + width]);
+    }
+    Image::new(width, height, pixels)
+}
+
+// Copies an image over part of another.
+fn paste(image: &mut Image, part: &Image, x: usize, y: usize) {
+    for (row, part_row) in image.pixels.chunks_exact_mut(image.width).skip(y).zip(part.pixels.chunks_exact(part.width)) {
+        row[x..x + part.width].copy_from_slice(part_row);
+    }
+}
+
+// Scales with nearest neighbour, so each pixel becomes a block.
+fn enlarge(image: &Image, scale: usize) -> Image {
+    let mut pixels = Vec::with_capacity(image.pixels.len() * scale * scale);
+    for row in image.pixels.chunks_exact(image.width) {
+        let row_start = pixels.len();
+        for &pixel in row {
+            pixels.extend(std::iter::repeat_n(pixel, scale));
+        }
+        for _ in 1..scale {
+            pixels.extend_from_within(row_start..row_start + image.width * scale);
+        }
+    }
+    Image::new(image.width * scale, image.height * scale, pixels)
+}
@@ -0,0 +1,133 @@
+// Checks semi-transparent pixels are compared as per the alpha mode and cutoff.
+
+use scalefx_rs::{AlphaMode, ColourMetric, Image, IndexedImage, ScaleFxOptions, png_io};
+
+const SHADOW: u32 = 0x00000060; // A soft black shadow.
+const GLOW: u32 = 0xffff0040; // A faint yellow glow.
+
+// A sprite on a transparent background, with a staircase of shadow to its lower right and a glow along its top.
+// Every edge within the shadow and glow is between semi-transparent pixels under the default cutoff.
+fn shadowed_sprite() -> Image {
+    let rows = [
+        "..gggggg..",
+        ".gRRRRRRg.",
+        ".gRRRRRRs.",
+        ".gRRRRRss.",
+        "..RRRRsss.",
+        "...RRsss..",
+        "....sss...",
+        "....ss....",
+    ];
+    let pixels = rows.iter().flat_map(|row| row.chars()).map(|c| match c {
+        'R' => 0xff0000ff,
+        's' => SHADOW,
+        'g' => GLOW,
+        _ => 0,
+    }).collect();
+    Image::new(10, 8, pixels)
+}
+
+// Scales with nearest neighbour, which is what ScaleFX does to regions it sees no edges in.
+fn enlarge(image: &Image) -> Image {
+    let pixels = (0..image.height * 3)
+        .flat_map(|y| (0..image.width * 3).map(move |x| (x, y)))
+        .map(|(x, y)| image.pixels[(y / 3) * image.width + x / 3])
+        .collect();
+    Image::new(image.width * 3, image.height * 3, pixels)
+}
+
+// The scaled pixels in the given source pixel's 3x3 block.
+fn block(image: &Image, x: usize, y: usize) -> Vec<u32> {
+    (0..3).flat_map(|dy| (0..3).map(move |dx| image.pixels[(y * 3 + dy) * image.width + x * 3 + dx])).collect()
+}
+
+#[test]
+fn premultiplied_distances() {
+    let metric = ColourMetric::Redmean;
+    let distance = |a, b| metric.distance_with_alpha(a, b, AlphaMode::Premultiplied, 1);
+    assert_eq!(distance(0, 0x12345600), 0., "Fully transparent pixels are all the same");
+    assert_eq!(distance(0, 0x000000ff), 1., "Solid black is as different as can be from transparent");
+    assert_eq!(distance(0, 0xffffffff), 1.);
+    assert!(distance(0, 0x00000010) < 0.1, "A faint pixel is close to transparent");
+    assert!(distance(SHADOW, 0x00000090) > 0.1, "Different strengths of the same colour are different");
+    assert_eq!(metric.distance(SHADOW, 0x00000090), 1., "But binary mode only sees transparent vs opaque");
+    assert!(distance(0xff000080, 0x0000ff80) < distance(0xff0000ff, 0x0000ffff), "Faint colours are closer together than solid ones");
+    assert_eq!(distance(0xff0000ff, 0x0000ffff), metric.distance(0xff0000ff, 0x0000ffff), "Opaque colours are compared as usual");
+}
+
+#[test]
+fn cutoff_sets_what_counts_as_transparent() {
+    let metric = ColourMetric::Redmean;
+    assert_eq!(metric.distance_with_alpha(SHADOW, 0, AlphaMode::Binary, 0x80), 0.);
+    assert_eq!(metric.distance_with_alpha(SHADOW, 0, AlphaMode::Binary, 0x60), 1.);
+    assert_eq!(metric.distance_with_alpha(SHADOW, 0, AlphaMode::Premultiplied, 0x61), 0.);
+    assert!(metric.distance_with_alpha(SHADOW, 0, AlphaMode::Premultiplied, 0x60) > 0.);
+    let mut options = ScaleFxOptions::default();
+    options.alpha_cutoff = 0;
+    assert!(options.validate().is_err());
+    assert_eq!(ScaleFxOptions::default().with_alpha_cutoff(0).alpha_cutoff, 1);
+}
+
+#[test]
+fn binary_mode_ignores_shadow_and_glow_edges() {
+    // Under the cutoff, the shadow and glow are the same as the background, so only the red's outline is smoothed.
+    let sprite = shadowed_sprite();
+    let scaled = sprite.scale3x(&ScaleFxOptions::default()).unwrap();
+    let shadow_only = |image: &Image| image.pixels.iter().map(|&pixel| if pixel == 0xff0000ff { 1 } else { 0 }).collect::<Vec<_>>();
+    let red_alone = Image::new(10, 8, sprite.pixels.iter().map(|&pixel| if pixel == 0xff0000ff { pixel } else { 0 }).collect());
+    assert_eq!(shadow_only(&scaled), shadow_only(&red_alone.scale3x(&ScaleFxOptions::default()).unwrap()));
+    // The tip of the shadow's staircase is left square.
+    assert_eq!(block(&scaled, 5, 7), vec![SHADOW; 9]);
+}
+
+#[test]
+fn premultiplied_mode_smooths_shadow_and_glow_edges() {
+    // The default cutoff would make the shadow and glow fully transparent, so let every alpha count.
+    // The shadow is only about 0.38 from transparent, so it needs a threshold below that to have edges against it.
+    let sprite = shadowed_sprite();
+    let options = ScaleFxOptions::default().with_threshold(0.3).with_alpha_cutoff(1);
+    let scaled = sprite.scale3x(&options.with_alpha_mode(AlphaMode::Premultiplied)).unwrap();
+    assert_eq!(block(&sprite.scale3x(&options.with_alpha_cutoff(0x80)).unwrap(), 5, 7), vec![SHADOW; 9], "Binary mode can't see the shadow at any threshold");
+    // The shadow's staircase against the background is smoothed, so its tip is no longer square.
+    assert!(block(&scaled, 5, 7).contains(&0), "{:08x?}", block(&scaled, 5, 7));
+    assert_ne!(scaled, enlarge(&sprite));
+    // Every colour is still one of the sprite's, with its alpha intact.
+    assert!(scaled.pixels.iter().all(|pixel| sprite.pixels.contains(pixel)));
+}
+
+#[test]
+fn lower_cutoff_makes_shadows_opaque_in_binary_mode() {
+    let sprite = shadowed_sprite();
+    let scaled = sprite.scale3x(&ScaleFxOptions::default().with_alpha_cutoff(0x40)).unwrap();
+    assert!(block(&scaled, 5, 7).contains(&0), "The shadow now has edges against the background");
+}
+
+#[test]
+fn simd_matches_scalar_for_every_cutoff() {
+    let mut images = vec![shadowed_sprite(), png_io::load_png("readme/RoboRed.png").unwrap()];
+    // Noise with every alpha, to catch any off-by-one at the cutoff.
+    let mut state = 0x9e3779b9u32;
+    images.push(Image::new(32, 32, (0..32 * 32).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    }).collect()));
+    for image in &images {
+        for cutoff in [1, 0x40, 0x60, 0x61, 0x80, 0xff] {
+            let options = ScaleFxOptions::default().with_alpha_cutoff(cutoff);
+            assert_eq!(image.scale3x(&options).unwrap(), image.scale3x(&options.with_simd(false)).unwrap(), "Cutoff {}", cutoff);
+        }
+    }
+}
+
+#[test]
+fn indexed_matches_rgba_with_premultiplied_alpha() {
+    let sprite = shadowed_sprite();
+    let palette = vec![0, 0xff0000ff, SHADOW, GLOW];
+    let indices = sprite.pixels.iter().map(|pixel| palette.iter().position(|colour| colour == pixel).unwrap() as u8).collect();
+    let indexed = IndexedImage::new(sprite.width, sprite.height, indices, palette);
+    for options in [ScaleFxOptions::default().with_alpha_mode(AlphaMode::Premultiplied).with_alpha_cutoff(1), ScaleFxOptions::default().with_alpha_cutoff(0x30)] {
+        assert_eq!(indexed.scale3x(&options).unwrap().to_image(), sprite.scale3x(&options).unwrap());
+    }
+}
@@ -0,0 +1,29 @@
+// Checks the library's entry points agree with each other.
+
+use scalefx_rs::{Image, ScaleFxOptions, scale3x, scale3x_with_options, scale9x, scale9x_with_options};
+
+// A red square on a blue background, with transparency down one side.
+fn square() -> Image {
+    let pixels = (0..6 * 5).map(|i| match (i % 6, i / 6) {
+        (0, _) => 0,
+        (2..=3, 1..=3) => 0xff0000ff,
+        _ => 0x0000ffff,
+    }).collect();
+    Image::new(6, 5, pixels)
+}
+
+#[test]
+fn functions_and_methods_match() {
+    let image = square();
+    let options = ScaleFxOptions::default();
+    let big = image.scale3x(&options).unwrap();
+    assert_eq!((big.width, big.height, big.pixels.len()), (18, 15, 18 * 15));
+    assert_eq!(scale3x(image.width, image.height, &image.pixels), (big.width, big.height, big.pixels.clone()));
+    assert_eq!(scale3x_with_options(image.width, image.height, &image.pixels, &options), (big.width, big.height, big.pixels.clone()));
+
+    let bigger = image.scale9x(&options).unwrap();
+    assert_eq!(bigger, big.scale3x(&options).unwrap(), "9x is 3x twice");
+    assert_eq!(scale9x(image.width, image.height, &image.pixels), (bigger.width, bigger.height, bigger.pixels.clone()));
+    assert_eq!(scale9x_with_options(image.width, image.height, &image.pixels, &options), (bigger.width, bigger.height, bigger.pixels));
+}
+
@@ -0,0 +1,125 @@
+// Checks animated PNGs are composited, scaled and re-encoded with their timings.
+
+mod common;
+
+use common::{BLUE, GREEN, RED, temp_path};
+use png::{BlendOp, ColorType, DisposeOp};
+use scalefx_rs::{Animation, AnimationFrame, Image, ScaleFxOptions, png_io};
+
+const HALF_BLUE: u32 = 0x0000ff80;
+
+fn to_bytes(pixels: &[u32]) -> Vec<u8> {
+    pixels.iter().flat_map(|pixel| pixel.to_be_bytes()).collect()
+}
+
+// Writes a 4x4 APNG whose frames only cover part of the canvas, to exercise each blend and dispose op.
+// It has a separate default image, which isn't part of the animation.
+fn write_partial_frames_apng(path: &str) {
+    let file = std::fs::File::create(path).unwrap();
+    let mut encoder = png::Encoder::new(file, 4, 4);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_animated(5, 2).unwrap();
+    encoder.set_sep_def_img(true).unwrap();
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&to_bytes(&[BLUE; 16])).unwrap();
+    let mut frame = |x, y, width, height, pixels: &[u32], blend, dispose, delay| {
+        writer.reset_frame_position().unwrap();
+        writer.set_frame_dimension(width, height).unwrap();
+        writer.set_frame_position(x, y).unwrap();
+        writer.set_blend_op(blend).unwrap();
+        writer.set_dispose_op(dispose).unwrap();
+        writer.set_frame_delay(delay, 1000).unwrap();
+        writer.write_image_data(&to_bytes(pixels)).unwrap();
+    };
+    // A red background, which stays.
+    frame(0, 0, 4, 4, &[RED; 16], BlendOp::Source, DisposeOp::None, 10);
+    // A green 2x2 in the top-left with a transparent hole that replaces what's underneath, cleared afterwards.
+    frame(0, 0, 2, 2, &[GREEN, GREEN, GREEN, 0], BlendOp::Source, DisposeOp::Background, 20);
+    // A blue 2x1 in the bottom-right drawn over the top, with a transparent pixel, undone afterwards.
+    frame(2, 3, 2, 1, &[BLUE, 0], BlendOp::Over, DisposeOp::Previous, 30);
+    // A half-transparent blue pixel over red and over nothing.
+    frame(0, 2, 2, 1, &[HALF_BLUE, HALF_BLUE], BlendOp::Over, DisposeOp::None, 40);
+    // Nothing new, to show the state after the previous frames' disposal.
+    frame(0, 0, 1, 1, &[0], BlendOp::Over, DisposeOp::None, 50);
+    writer.finish().unwrap();
+}
+
+#[test]
+fn frames_are_composited_by_blend_and_dispose_ops() {
+    let path = temp_path("partial_frames.png");
+    write_partial_frames_apng(&path);
+    let animation = png_io::load_apng(&path).unwrap();
+    assert_eq!(animation.frames.len(), 5, "The default image should be skipped");
+    let frames: Vec<&Vec<u32>> = animation.frames.iter().map(|frame| &frame.image.pixels).collect();
+    let mut expected = vec![RED; 16];
+    assert_eq!(frames[0], &expected);
+    expected[0] = GREEN; expected[1] = GREEN; expected[4] = GREEN; expected[5] = 0; // Source replaces, even with transparency.
+    assert_eq!(frames[1], &expected);
+    expected[0] = 0; expected[1] = 0; expected[4] = 0; // Background disposal clears the frame's area.
+    expected[14] = BLUE; // Over leaves the red showing through the transparent pixel.
+    assert_eq!(frames[2], &expected);
+    expected[14] = RED; // Previous disposal restores what was there.
+    expected[8] = 0x7f0080ff; // Half blue over red.
+    expected[9] = 0x7f0080ff;
+    assert_eq!(frames[3], &expected);
+    assert_eq!(frames[4], &expected);
+    let delays: Vec<(u16, u16)> = animation.frames.iter().map(|frame| (frame.delay_numerator, frame.delay_denominator)).collect();
+    assert_eq!(delays, [(10, 1000), (20, 1000), (30, 1000), (40, 1000), (50, 1000)]);
+    assert_eq!(animation.plays, 2);
+}
+
+#[test]
+fn half_transparent_over_transparent_keeps_its_colour() {
+    let path = temp_path("over_transparent.png");
+    let file = std::fs::File::create(&path).unwrap();
+    let mut encoder = png::Encoder::new(file, 1, 1);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_animated(2, 0).unwrap();
+    let mut writer = encoder.write_header().unwrap();
+    writer.set_dispose_op(DisposeOp::Background).unwrap();
+    writer.write_image_data(&to_bytes(&[RED])).unwrap();
+    writer.set_blend_op(BlendOp::Over).unwrap();
+    writer.write_image_data(&to_bytes(&[HALF_BLUE])).unwrap();
+    writer.finish().unwrap();
+    let animation = png_io::load_apng(&path).unwrap();
+    assert_eq!(animation.frames[0].image.pixels, [RED]);
+    assert_eq!(animation.frames[1].image.pixels, [HALF_BLUE]);
+    assert_eq!(animation.plays, 0);
+}
+
+#[test]
+fn scaled_apng_keeps_timings() {
+    let in_path = temp_path("scale_in.png");
+    let out_path = temp_path("scale_out.png");
+    write_partial_frames_apng(&in_path);
+    let animation = png_io::load_apng(&in_path).unwrap();
+    let scaled = animation.scale3x(&ScaleFxOptions::default()).unwrap();
+    png_io::save_apng(&scaled, &out_path).unwrap();
+    let reloaded = png_io::load_apng(&out_path).unwrap();
+    assert_eq!(reloaded, scaled);
+    for (frame, original) in reloaded.frames.iter().zip(&animation.frames) {
+        assert_eq!((frame.image.width, frame.image.height), (12, 12));
+        assert_eq!((frame.delay_numerator, frame.delay_denominator), (original.delay_numerator, original.delay_denominator));
+    }
+    // Viewers without APNG support show the first frame.
+    assert_eq!(png_io::load_png(&out_path).unwrap(), scaled.frames[0].image);
+}
+
+#[test]
+fn still_png_loads_as_one_frame() {
+    let animation = png_io::load_apng("readme/Keen.png").unwrap();
+    assert_eq!(animation.frames.len(), 1);
+    assert_eq!(animation.frames[0].image, png_io::load_png("readme/Keen.png").unwrap());
+    assert_eq!(animation.plays, 1);
+}
+
+#[test]
+fn loop_counts_round_trip() {
+    let path = temp_path("loops.png");
+    for plays in [0, 1, 7] {
+        let frame = AnimationFrame { image: Image::new(2, 1, vec![RED, 0]), delay_numerator: 1, delay_denominator: 24 };
+        let animation = Animation { frames: vec![frame.clone(), AnimationFrame { delay_numerator: 2, ..frame }], plays };
+        png_io::save_apng(&animation, &path).unwrap();
+        assert_eq!(png_io::load_apng(&path).unwrap(), animation);
+    }
+}
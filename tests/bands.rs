@@ -0,0 +1,44 @@
+// Checks processing the image in bands gives exactly the same output as processing it whole.
+
+mod common;
+
+use common::load;
+use scalefx_rs::ScaleFxOptions;
+
+#[test]
+fn bands_match_whole() {
+    let whole = ScaleFxOptions::default();
+    for name in ["Keen", "Dopefish", "sq3.fixed"] {
+        let image = load(name);
+        let expected = image.scale3x(&whole).unwrap();
+        // Bands thinner than the passes' reach, uneven ones, and one taller than the image.
+        for band_rows in [1, 2, 3, 7, 64, 1000] {
+            assert_eq!(image.scale3x(&whole.with_band_rows(band_rows)).unwrap(), expected, "{} in bands of {}", name, band_rows);
+        }
+    }
+}
+
+#[test]
+fn bands_match_whole_at_9x() {
+    let image = load("Bip");
+    let whole = ScaleFxOptions::default();
+    assert_eq!(image.scale9x(&whole.with_band_rows(5)).unwrap(), image.scale9x(&whole).unwrap());
+}
+
+#[test]
+fn bands_match_whole_with_other_options() {
+    let image = load("Dopefish");
+    let options = [
+        ScaleFxOptions::default().with_threads(3),
+        ScaleFxOptions::default().with_threshold(0.2),
+        {
+            let mut options = ScaleFxOptions::default();
+            options.is_filter_aa_enabled = false;
+            options.filter_corners = false;
+            options
+        },
+    ];
+    for options in options {
+        assert_eq!(image.scale3x(&options.with_band_rows(4)).unwrap(), image.scale3x(&options).unwrap(), "{:?}", options);
+    }
+}
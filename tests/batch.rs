@@ -0,0 +1,116 @@
+// Checks the binary's batch mode: which files it finds, where it writes them, what it skips, and its exit codes.
+
+use scalefx_rs::{ScaleFxOptions, png_io};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// A fresh directory for a test, removed first in case an earlier run left it behind.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("scalefx-batch-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Copies a sprite from the readme into the directory, under the given path.
+fn copy_sprite(name: &str, dir: &Path, path: &str) {
+    let to = dir.join(path);
+    std::fs::create_dir_all(to.parent().unwrap()).unwrap();
+    std::fs::copy(format!("readme/{}.png", name), to).unwrap();
+}
+
+fn run(args: &[&Path]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_scalefx-rs")).arg("--scale").arg("3").args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+// Whether the file is the sprite scaled to 3x. Fully transparent pixels may be saved as any colour, so theirs is ignored.
+fn is_scaled(name: &str, path: &Path) -> bool {
+    let opaque = |pixels: &[u32]| pixels.iter().map(|&pixel| if pixel & 0xff == 0 { 0 } else { pixel }).collect::<Vec<_>>();
+    let expected = png_io::load_png(&format!("readme/{}.png", name)).unwrap().scale3x(&ScaleFxOptions::default()).unwrap();
+    png_io::load_png(path.to_str().unwrap()).is_ok_and(|scaled| (scaled.width, scaled.height, opaque(&scaled.pixels)) == (expected.width, expected.height, opaque(&expected.pixels)))
+}
+
+#[test]
+fn directories_are_mirrored() {
+    let dir = temp_dir("mirrored");
+    copy_sprite("Bip", &dir, "in/Bip.png");
+    copy_sprite("BloogletR", &dir, "in/enemies/BloogletR.png");
+    let output = run(&[Path::new("-q"), Path::new("-o"), &dir.join("out"), &dir.join("in")]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "", "Quiet");
+    assert!(is_scaled("Bip", &dir.join("out/Bip.png")));
+    assert!(is_scaled("BloogletR", &dir.join("out/enemies/BloogletR.png")));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn globs_match_file_names() {
+    let dir = temp_dir("glob");
+    for name in ["Bip", "BloogletR", "BloogletG", "Bloog"] {
+        copy_sprite(name, &dir, &format!("in/{}.png", name));
+    }
+    let output = run(&[Path::new("-o"), &dir.join("out"), &dir.join("in/Bloog*t?.png")]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("Processed: 2, skipped: 0, failed: 0"), "{}", stdout(&output));
+    assert!(is_scaled("BloogletR", &dir.join("out/BloogletR.png")));
+    assert!(is_scaled("BloogletG", &dir.join("out/BloogletG.png")));
+    assert!(!dir.join("out/Bloog.png").exists() && !dir.join("out/Bip.png").exists());
+
+    // Stars that have to give back characters they first took.
+    let output = run(&[Path::new("-o"), &dir.join("out2"), &dir.join("in/*o*o*R.png")]);
+    assert!(stdout(&output).contains("Processed: 1, skipped: 0, failed: 0"), "{}", stdout(&output));
+    assert!(dir.join("out2/BloogletR.png").exists());
+
+    let output = run(&[Path::new("-o"), &dir.join("out3"), &dir.join("in/*.gif")]);
+    assert_eq!(output.status.code(), Some(1), "Nothing matches");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn newer_outputs_are_skipped() {
+    let dir = temp_dir("skip");
+    copy_sprite("Bip", &dir, "in/Bip.png");
+    copy_sprite("BloogletR", &dir, "in/BloogletR.png");
+    let args = [Path::new("-o"), &dir.join("out"), &dir.join("in")];
+    assert!(stdout(&run(&args)).contains("Processed: 2, skipped: 0, failed: 0"));
+    assert!(stdout(&run(&args)).contains("Processed: 0, skipped: 2, failed: 0"));
+    copy_sprite("Bip", &dir, "in/Bip.png");
+    assert!(stdout(&run(&args)).contains("Processed: 1, skipped: 1, failed: 0"), "The changed input is scaled again");
+    let forced = run(&[Path::new("--force"), Path::new("-o"), &dir.join("out"), &dir.join("in")]);
+    assert!(stdout(&forced).contains("Processed: 2, skipped: 0, failed: 0"), "{}", stdout(&forced));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn failures_are_counted_and_exit_non_zero() {
+    let dir = temp_dir("failures");
+    copy_sprite("Bip", &dir, "in/Bip.png");
+    std::fs::write(dir.join("in/Broken.png"), b"not a png").unwrap();
+    let output = run(&[Path::new("-o"), &dir.join("out"), &dir.join("in")]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("Processed: 1, skipped: 0, failed: 1"), "{}", stdout(&output));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Broken.png"));
+    assert!(is_scaled("Bip", &dir.join("out/Bip.png")), "The other inputs are still scaled");
+
+    let output = run(&[Path::new("-o"), &dir.join("out"), &dir.join("missing.png")]);
+    assert_eq!(output.status.code(), Some(1), "A missing input");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn pass_dumps_mirror_the_output_directories() {
+    let dir = temp_dir("dumps");
+    copy_sprite("Bip", &dir, "in/Bip.png");
+    copy_sprite("Keen", &dir, "in/other/Bip.png");
+    let output = run(&[Path::new("-q"), Path::new("--dump-passes"), &dir.join("dumps"), Path::new("-o"), &dir.join("out"), &dir.join("in")]);
+    assert!(output.status.success());
+    let dump_width = |path: &str| png_io::load_png(dir.join("dumps").join(path).to_str().unwrap()).unwrap().width;
+    let width = |name: &str| png_io::load_png(&format!("readme/{}.png", name)).unwrap().width * 3;
+    assert_eq!(dump_width("Bip.tags.png"), width("Bip"));
+    assert_eq!(dump_width("other/Bip.tags.png"), width("Keen"), "The same name in another directory");
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,59 @@
+// Checks how each border mode treats the edges of the image.
+
+use scalefx_rs::{BorderMode, Image, ScaleFxOptions, png_io};
+
+const MODES: [BorderMode; 4] = [BorderMode::Transparent, BorderMode::Clamp, BorderMode::Mirror, BorderMode::Wrap];
+
+fn options(border_mode: BorderMode) -> ScaleFxOptions {
+    ScaleFxOptions::default().with_border_mode(border_mode)
+}
+
+// An opaque image with a diagonal edge running into its corners.
+fn diagonal(size: usize) -> Image {
+    let pixels = (0..size * size).map(|i| if i % size > i / size { 0x3060c0ff } else { 0xe0d080ff }).collect();
+    Image::new(size, size, pixels)
+}
+
+#[test]
+fn opaque_edges_stay_opaque_unless_transparent() {
+    let image = diagonal(7);
+    for mode in MODES {
+        let scaled = image.scale9x(&options(mode)).unwrap();
+        let transparent = scaled.pixels.iter().filter(|&&pixel| pixel & 0xff == 0).count();
+        if mode == BorderMode::Transparent {
+            assert!(transparent > 0, "The corners should be smoothed into the transparent border");
+        } else {
+            assert_eq!(transparent, 0, "{:?} shouldn't bleed transparency into the edges", mode);
+        }
+    }
+}
+
+#[test]
+fn solid_image_stays_solid() {
+    let image = Image::new(5, 4, vec![0x336699ff; 20]);
+    for mode in [BorderMode::Clamp, BorderMode::Mirror, BorderMode::Wrap] {
+        let scaled = image.scale3x(&options(mode)).unwrap();
+        assert!(scaled.pixels.iter().all(|&pixel| pixel == 0x336699ff), "{:?}", mode);
+    }
+}
+
+#[test]
+fn tiny_images_scale_in_every_mode() {
+    for mode in MODES {
+        for (width, height) in [(1, 1), (1, 3), (2, 2), (3, 1)] {
+            let pixels = (0..width * height).map(|i| (0x10203040 * i as u32) | 0xff).collect();
+            let scaled = Image::new(width, height, pixels).scale9x(&options(mode)).unwrap();
+            assert_eq!((scaled.width, scaled.height), (width * 9, height * 9));
+        }
+    }
+}
+
+#[test]
+fn bands_and_threads_match_in_every_mode() {
+    let image = png_io::load_png("readme/Keen.png").unwrap();
+    for mode in MODES {
+        let whole = image.scale3x(&options(mode)).unwrap();
+        let banded = image.scale3x(&options(mode).with_band_rows(3).with_threads(2)).unwrap();
+        assert_eq!(whole, banded, "{:?}", mode);
+    }
+}
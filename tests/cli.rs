@@ -0,0 +1,108 @@
+// Checks the binary's argument parsing and exit statuses: 0 on success, 1 on a runtime error and 2 on bad arguments.
+
+use scalefx_rs::png_io;
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_scalefx-rs")).args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+fn temp_path(name: &str) -> String {
+    std::env::temp_dir().join(format!("scalefx-cli-{}-{}", std::process::id(), name)).to_str().unwrap().to_string()
+}
+
+// Asserts the arguments are rejected before anything is scaled, with the error and the usage.
+fn assert_bad_arguments(args: &[&str], error: &str) {
+    let output = run(args);
+    assert_eq!(output.status.code(), Some(2), "{:?}", args);
+    assert!(stderr(&output).starts_with(&format!("Error: {}", error)), "{:?}: {}", args, stderr(&output));
+    assert!(stderr(&output).contains("Usage:"), "{:?}", args);
+}
+
+#[test]
+fn help_prints_the_usage() {
+    for flag in ["-h", "--help"] {
+        let output = run(&[flag]);
+        assert!(output.status.success());
+        assert!(stdout(&output).starts_with("Usage:"));
+    }
+    assert!(stdout(&run(&["--scale", "3", "--help", "in.png"])).starts_with("Usage:"), "Help wins over everything else");
+}
+
+#[test]
+fn version_prints_the_version() {
+    for flag in ["-V", "--version"] {
+        let output = run(&[flag]);
+        assert!(output.status.success());
+        assert_eq!(stdout(&output), format!("scalefx {}\n", env!("CARGO_PKG_VERSION")));
+    }
+}
+
+#[test]
+fn upscale_scales_the_input_to_the_output() {
+    let out = temp_path("upscale.png");
+    let output = run(&["-q", "--scale", "3", "--threshold", "0.3", "--no-aa-filter", "--no-corner-filter", "readme/Bip.png", &out]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "", "Quiet");
+    let (input, scaled) = (png_io::load_png("readme/Bip.png").unwrap(), png_io::load_png(&out).unwrap());
+    std::fs::remove_file(&out).unwrap();
+    assert_eq!((scaled.width, scaled.height), (input.width * 3, input.height * 3));
+
+    let output = run(&["--scale", "3", "readme/Bip.png", &out]);
+    std::fs::remove_file(&out).unwrap();
+    assert!(stdout(&output).contains("ScaleFX-rs Pixel Art Upscaler"), "Not quiet");
+}
+
+#[test]
+fn unknown_options_are_rejected() {
+    assert_bad_arguments(&["--bogus", "in.png", "out.png"], "Unknown option: --bogus");
+    assert_bad_arguments(&["-x", "in.png", "out.png"], "Unknown option: -x");
+}
+
+#[test]
+fn missing_values_are_rejected() {
+    assert_bad_arguments(&["in.png", "out.png", "--scale"], "--scale needs a value");
+    assert_bad_arguments(&["in.png", "out.png", "--threshold"], "--threshold needs a value");
+    assert_bad_arguments(&["in.png", "-o"], "-o needs a value");
+    assert_bad_arguments(&["in.png", "--out-dir"], "--out-dir needs a value");
+}
+
+#[test]
+fn bad_values_are_rejected() {
+    for factor in ["0", "x", "-3", "2.5"] {
+        assert_bad_arguments(&["--scale", factor, "in.png", "out.png"], "--scale must be");
+    }
+    assert_bad_arguments(&["--threshold", "abc", "in.png", "out.png"], "--threshold must be a number");
+    assert_bad_arguments(&["--threshold", "2", "in.png", "out.png"], "Invalid options: threshold");
+    assert_bad_arguments(&["--threads", "many", "in.png", "out.png"], "--threads must be a whole number, but was many");
+    assert_bad_arguments(&["--band-rows", "-1", "in.png", "out.png"], "--band-rows must be a whole number, but was -1");
+}
+
+#[test]
+fn paths_must_be_an_input_and_an_output() {
+    assert_bad_arguments(&[], "Expected an input and an output path, but got 0");
+    assert_bad_arguments(&["in.png"], "Expected an input and an output path, but got 1");
+    assert_bad_arguments(&["a.png", "b.png", "c.png"], "Expected an input and an output path, but got 3");
+}
+
+#[test]
+fn batch_mode_needs_an_input() {
+    assert_bad_arguments(&["-o", "out"], "Expected at least one input");
+}
+
+#[test]
+fn missing_inputs_exit_with_status_1() {
+    let out = temp_path("missing.png");
+    let output = run(&["does-not-exist.png", &out]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("Error: "));
+    assert!(!std::path::Path::new(&out).exists());
+}
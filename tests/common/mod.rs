@@ -0,0 +1,58 @@
+// Fixtures shared by the integration tests. Each test file only uses some of them, hence the allow.
+#![allow(dead_code)]
+
+use scalefx_rs::{Image, png_io};
+
+pub const RED: u32 = 0xff0000ff;
+pub const GREEN: u32 = 0x00ff00ff;
+pub const BLUE: u32 = 0x0000ffff;
+
+// A path in Cargo's scratch directory for the integration tests.
+pub fn temp_path(name: &str) -> String {
+    format!("{}/{}", env!("CARGO_TARGET_TMPDIR"), name)
+}
+
+// A sample sprite from the readme folder.
+pub fn load(name: &str) -> Image {
+    png_io::load_png(&format!("readme/{}.png", name)).unwrap()
+}
+
+// Every sample sprite in the readme folder by name, besides the already-scaled ones (which were made by an older version).
+pub fn sprites() -> Vec<(String, Image)> {
+    let mut names: Vec<String> = std::fs::read_dir("readme").unwrap()
+        .map(|entry| entry.unwrap().file_name().to_str().unwrap().to_string())
+        .filter(|name| name.ends_with(".png") && !name.ends_with(".big.png"))
+        .map(|name| name.trim_end_matches(".png").to_string())
+        .collect();
+    names.sort();
+    names.into_iter().map(|name| {
+        let image = load(&name);
+        (name, image)
+    }).collect()
+}
+
+// The image's pixels with the fully transparent ones zeroed, as they may be saved or scaled as any colour with no alpha.
+pub fn without_transparent_colours(image: &Image) -> Vec<u32> {
+    image.pixels.iter().map(|&pixel| if pixel & 0xff == 0 { 0 } else { pixel }).collect()
+}
+
+// The image with every colour swapped for another, such as a game would do to make an enemy variant.
+pub fn recolour(image: &Image, swap: impl Fn(u32) -> u32) -> Image {
+    Image::new(image.width, image.height, image.pixels.iter().map(|&pixel| if pixel & 0xff == 0 { pixel } else { swap(pixel) }).collect())
+}
+
+// Rotates the red, green and blue channels, which changes how far apart the colours are.
+pub fn rotate_channels(colour: u32) -> u32 {
+    (colour >> 24) << 8 | (colour >> 8 & 0xff) << 16 | (colour >> 16 & 0xff) << 24 | (colour & 0xff)
+}
+
+// A staircase of red on transparency, which has corners for ScaleFX to smooth.
+pub fn staircase() -> Image {
+    let rows = [
+        "RRRRR...",
+        "RRRR....",
+        "RRR.....",
+        "RR......",
+    ];
+    Image::new(8, 4, rows.iter().flat_map(|row| row.chars()).map(|c| if c == 'R' { RED } else { 0 }).collect())
+}
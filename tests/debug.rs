@@ -0,0 +1,76 @@
+// Checks the debug images of each pass line up with the output and show what the passes saw.
+
+mod common;
+
+use common::{RED, load, staircase};
+use scalefx_rs::{BorderMode, Image, ScaleFxOptions, dump_passes};
+
+fn subpixel(image: &Image, x: usize, y: usize) -> u32 {
+    image.pixels[y * image.width + x]
+}
+
+#[test]
+fn every_image_is_at_3x() {
+    let image = load("Keen");
+    let options = ScaleFxOptions::default();
+    let dump = dump_passes(&image, &options).unwrap();
+    let names: Vec<&str> = dump.images().iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, ["distances", "corner-strengths", "corners", "horizontal-edges", "vertical-edges", "orientation", "tags", "output"]);
+    for (name, pass) in dump.images() {
+        assert_eq!((pass.width, pass.height), (image.width * 3, image.height * 3), "{}", name);
+    }
+    assert_eq!(dump.output, image.scale3x(&options).unwrap());
+    // The output follows the options, including the hybrid pass.
+    assert_eq!(dump_passes(&image, &options.with_hybrid(true)).unwrap().output, image.scale3x(&options.with_hybrid(true)).unwrap());
+}
+
+#[test]
+fn flat_images_show_nothing() {
+    let image = Image::new(4, 3, vec![RED; 12]);
+    let dump = dump_passes(&image, &ScaleFxOptions::default().with_border_mode(BorderMode::Clamp)).unwrap();
+    let faded = subpixel(&dump.tags, 1, 1);
+    // Each pixel's middle is itself, faded, and every distance around it is zero: black.
+    assert!(dump.distances.pixels.iter().enumerate().all(|(i, &pixel)| {
+        let is_middle = (i % dump.distances.width) % 3 == 1 && (i / dump.distances.width) % 3 == 1;
+        pixel == if is_middle { faded } else { 0x000000ff }
+    }));
+    for pass in [&dump.corner_strengths, &dump.corners, &dump.horizontal_edges, &dump.vertical_edges, &dump.orientation, &dump.tags] {
+        assert!(pass.pixels.iter().all(|&pixel| pixel == faded));
+    }
+}
+
+#[test]
+fn distances_are_shown_on_both_sides() {
+    let dump = dump_passes(&staircase(), &ScaleFxOptions::default()).unwrap();
+    // Red against transparency is as different as can be: white. Red against red is black.
+    assert_eq!(subpixel(&dump.distances, 4 * 3 + 2, 1), 0xffffffff, "The last red in the top row, on its right");
+    assert_eq!(subpixel(&dump.distances, 5 * 3, 1), 0xffffffff, "The transparency after it, on its left");
+    assert_eq!(subpixel(&dump.distances, 3 * 3 + 2, 1), 0x000000ff);
+    assert_eq!(subpixel(&dump.distances, 3 + 1, 3 * 3 + 2), 0xffffffff, "The bottom of the image is against the transparent border");
+}
+
+#[test]
+fn staircases_show_their_corners_and_tags() {
+    let image = staircase();
+    let dump = dump_passes(&image, &ScaleFxOptions::default()).unwrap();
+    let faded = |pass: &Image| (0..pass.pixels.len()).filter(|&i| pass.pixels[i] == dump.tags.pixels[i]).count();
+    assert!(faded(&dump.corner_strengths) < dump.tags.pixels.len(), "Some corners have strength");
+    assert!(dump.corners.pixels.contains(&0xe02020ff), "Some corners are smoothed");
+    // Wherever the output differs from simply enlarging the image, the tags show it's copied from a neighbour.
+    for y in 0..dump.output.height {
+        for x in 0..dump.output.width {
+            if subpixel(&dump.output, x, y) != subpixel(&image, x / 3, y / 3) {
+                let tag = subpixel(&dump.tags, x, y);
+                assert!([0xff0000ff, 0x00c000ff, 0x0000ffff, 0xffd000ff, 0x800000ff, 0x006000ff, 0x000080ff, 0x806800ff].contains(&tag), "{},{}", x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn invalid_options_are_an_error() {
+    let mut options = ScaleFxOptions::default();
+    options.threshold = 2.;
+    assert!(dump_passes(&staircase(), &options).is_err());
+    assert!(dump_passes(&Image::new(2, 2, vec![0; 3]), &ScaleFxOptions::default()).is_err());
+}
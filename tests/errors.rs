@@ -0,0 +1,47 @@
+// Checks the fallible API reports bad dimensions as errors rather than panicking, and otherwise matches the
+// panicking one.
+
+use scalefx_rs::{Image, ScaleFxError, ScaleFxOptions, scale3x, scale9x, try_scale3x, try_scale9x};
+
+const PIXELS: [u32; 6] = [0xff0000ff, 0x0000ffff, 0, 0x0000ffff, 0xff0000ff, 0xff0000ff];
+
+#[test]
+fn valid_images_match_the_panicking_api() {
+    let options = ScaleFxOptions::default();
+    let (width, height, pixels) = try_scale3x(3, 2, &PIXELS, &options).unwrap();
+    assert_eq!((width, height, pixels.len()), (9, 6, 54));
+    assert_eq!((width, height, pixels), scale3x(3, 2, &PIXELS));
+    assert_eq!(try_scale9x(3, 2, &PIXELS, &options).unwrap(), scale9x(3, 2, &PIXELS));
+}
+
+#[test]
+fn empty_images_are_an_error() {
+    let options = ScaleFxOptions::default();
+    assert!(matches!(try_scale3x(0, 0, &[], &options), Err(ScaleFxError::EmptyImage)));
+    assert!(matches!(try_scale3x(0, 5, &[], &options), Err(ScaleFxError::EmptyImage)));
+    assert!(matches!(try_scale9x(5, 0, &[], &options), Err(ScaleFxError::EmptyImage)));
+    assert!(matches!(Image::new(0, 0, Vec::new()).scale3x(&options), Err(ScaleFxError::EmptyImage)));
+}
+
+#[test]
+fn mismatched_pixel_counts_are_an_error() {
+    let options = ScaleFxOptions::default();
+    assert!(matches!(try_scale3x(2, 2, &PIXELS[..3], &options), Err(ScaleFxError::DimensionMismatch { width: 2, height: 2, pixels: 3 })));
+    assert!(matches!(try_scale3x(2, 2, &PIXELS, &options), Err(ScaleFxError::DimensionMismatch { width: 2, height: 2, pixels: 6 })), "Too many");
+    assert!(matches!(try_scale9x(3, 3, &PIXELS, &options), Err(ScaleFxError::DimensionMismatch { .. })));
+    assert!(matches!(Image::new(4, 1, PIXELS.to_vec()).scale9x(&options), Err(ScaleFxError::DimensionMismatch { .. })));
+}
+
+#[test]
+fn overflowing_widths_are_an_error() {
+    // The pixel count can't reach width x height when that overflows, rather than it wrapping round to match.
+    let options = ScaleFxOptions::default();
+    assert!(matches!(try_scale3x(usize::MAX, 2, &PIXELS[..2], &options), Err(ScaleFxError::DimensionMismatch { .. })));
+    assert!(matches!(try_scale9x(usize::MAX / 2 + 1, 2, &[], &options), Err(ScaleFxError::DimensionMismatch { .. })));
+}
+
+#[test]
+#[should_panic(expected = "Expected 2 x 2 = 4 pixels, but got 3")]
+fn the_panicking_api_panics_with_the_error() {
+    scale3x(2, 2, &PIXELS[..3]);
+}
@@ -0,0 +1,92 @@
+// Checks the per-pixel explanations match what the scale actually did.
+
+mod common;
+
+use common::{RED, load, staircase};
+use scalefx_rs::{BorderMode, Image, ScaleFxError, ScaleFxOptions, dump_passes, explain};
+
+// Where each tag copies from, relative to the pixel, as per the Explanation docs.
+const TAG_OFFSETS: [(isize, isize); 9] = [(0, 0), (-1, 0), (-2, 0), (1, 0), (2, 0), (0, -1), (0, -2), (0, 1), (0, 2)];
+
+#[test]
+fn outputs_match_scaling() {
+    for options in [ScaleFxOptions::default(), ScaleFxOptions::default().with_hybrid(true).with_border_mode(BorderMode::Clamp)] {
+        let image = load("Keen");
+        let scaled = image.scale3x(&options).unwrap();
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let explanation = explain(&image, x, y, &options).unwrap();
+                let block: [[u32; 3]; 3] = std::array::from_fn(|spy| std::array::from_fn(|spx| scaled.pixels[(y * 3 + spy) * scaled.width + x * 3 + spx]));
+                assert_eq!(explanation.output, block, "{},{}", x, y);
+                assert_eq!(explanation.neighbourhood[2][2], image.pixels[y * image.width + x]);
+            }
+        }
+    }
+}
+
+#[test]
+fn tags_say_where_the_output_came_from() {
+    let image = staircase();
+    let options = ScaleFxOptions::default();
+    let dump = dump_passes(&image, &options).unwrap();
+    let mut smoothed = 0;
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let explanation = explain(&image, x, y, &options).unwrap();
+            for spy in 0..3 {
+                for spx in 0..3 {
+                    let tag = explanation.tags[spy][spx];
+                    let (dx, dy) = TAG_OFFSETS[tag as usize];
+                    let source = explanation.neighbourhood[(2 + dy) as usize][(2 + dx) as usize];
+                    assert_eq!(explanation.output[spy][spx], source, "{},{} subpixel {},{}", x, y, spx, spy);
+                    // The same tags as the debug image shows.
+                    let is_faded = dump.tags.pixels[(y * 3 + spy) * dump.tags.width + x * 3 + spx] == dump.tags.pixels[(y * 3 + 1) * dump.tags.width + x * 3 + 1];
+                    assert_eq!(tag == 0, is_faded, "{},{} subpixel {},{}", x, y, spx, spy);
+                }
+            }
+            if explanation.tags.iter().flatten().any(|&tag| tag != 0) {
+                assert!(explanation.corners.contains(&true), "{},{}: smoothing needs a resolved corner", x, y);
+                assert!(!explanation.levels.is_empty(), "{},{}: smoothing needs a level", x, y);
+                smoothed += 1;
+            }
+        }
+    }
+    assert!(smoothed > 0, "Some of the staircase is smoothed");
+}
+
+#[test]
+fn staircase_steps_are_corners() {
+    // The corner of the top step, with red up-left of its bottom right junction and transparency on the other sides.
+    let explanation = explain(&staircase(), 4, 0, &ScaleFxOptions::default()).unwrap();
+    assert_eq!(explanation.distances[3], 1., "Red against transparency to the right is as different as can be");
+    assert!(explanation.corner_strengths[2] > 0., "{:?}", explanation.corner_strengths);
+    assert!(explanation.levels.iter().all(|level| level.starts_with("lvl")));
+    let report = explanation.to_string();
+    for section in ["Pixel 4,0", "Neighbourhood", "Distances", "Corner strengths", "Junctions", "Corners", "Levels", "Subpixels"] {
+        assert!(report.contains(section), "{}", report);
+    }
+}
+
+#[test]
+fn flat_images_have_nothing_to_explain() {
+    let image = Image::new(5, 5, vec![RED; 25]);
+    let explanation = explain(&image, 2, 2, &ScaleFxOptions::default()).unwrap();
+    assert_eq!(explanation.neighbourhood, [[RED; 5]; 5]);
+    assert_eq!(explanation.distances, [0.; 4]);
+    assert_eq!(explanation.corner_strengths, [0.; 4]);
+    assert_eq!(explanation.junction_votes, [[false; 4]; 4]);
+    assert_eq!(explanation.corners, [false; 4]);
+    assert!(explanation.levels.is_empty());
+    assert_eq!(explanation.tags, [[0; 3]; 3]);
+    assert_eq!(explanation.output, [[RED; 3]; 3]);
+}
+
+#[test]
+fn pixels_outside_the_image_are_an_error() {
+    let image = staircase();
+    assert!(matches!(explain(&image, 8, 0, &ScaleFxOptions::default()), Err(ScaleFxError::PixelOutOfBounds { x: 8, y: 0, width: 8, height: 4 })));
+    assert!(matches!(explain(&image, 0, 4, &ScaleFxOptions::default()), Err(ScaleFxError::PixelOutOfBounds { x: 0, y: 4, .. })));
+    let mut options = ScaleFxOptions::default();
+    options.threshold = 2.;
+    assert!(explain(&image, 0, 0, &options).is_err());
+}
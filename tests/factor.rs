@@ -0,0 +1,134 @@
+// Checks scaling by any whole number factor.
+
+mod common;
+
+use common::load;
+use scalefx_rs::{Image, IndexedImage, ScaleFxError, ScaleFxOptions, SheetLayout, factor, png_io, scale_sheet};
+
+#[test]
+fn native_factors_are_powers_of_three() {
+    assert_eq!((1..=10).map(factor::native_factor).collect::<Option<Vec<_>>>().unwrap(), [1, 3, 3, 9, 9, 9, 9, 9, 9, 27]);
+    assert_eq!((0..=28).filter(|&factor| factor::is_native(factor)).collect::<Vec<_>>(), [1, 3, 9, 27]);
+}
+
+#[test]
+fn native_factors_that_overflow_are_none() {
+    let largest = 3usize.pow(usize::MAX.ilog(3));
+    assert_eq!(factor::native_factor(largest), Some(largest));
+    assert_eq!(factor::native_factor(largest + 1), None);
+    assert_eq!(factor::native_factor(usize::MAX), None);
+    assert!(!factor::is_native(usize::MAX));
+}
+
+#[test]
+fn native_factors_match_scale3x_and_scale9x() {
+    let image = load("Keen");
+    let options = ScaleFxOptions::default();
+    assert_eq!(image.scale(1, &options).unwrap(), image);
+    assert_eq!(image.scale(3, &options).unwrap(), image.scale3x(&options).unwrap());
+    assert_eq!(image.scale(9, &options).unwrap(), image.scale9x(&options).unwrap());
+}
+
+#[test]
+fn zero_is_an_error() {
+    assert!(load("Keen").scale(0, &ScaleFxOptions::default()).is_err());
+}
+
+#[test]
+fn factors_too_large_for_a_native_factor_are_an_error() {
+    let options = ScaleFxOptions::default();
+    let image = Image::new(1, 1, vec![0xff0000ff]);
+    assert!(matches!(image.scale(usize::MAX, &options), Err(ScaleFxError::InvalidOptions(_))));
+    assert!(factor::try_scale(1, 1, &[0xff0000ff], usize::MAX, &options).is_err());
+    assert!(IndexedImage::new(1, 1, vec![0], vec![0xff0000ff]).scale(usize::MAX, &options).is_err());
+    assert!(scale_sheet(&image, &SheetLayout::new(1, 1), usize::MAX, &options).is_err());
+}
+
+#[test]
+fn factors_too_large_for_the_output_are_an_error() {
+    // A native factor, but one whose output wouldn't fit in memory.
+    let factor = 3usize.pow(20);
+    let options = ScaleFxOptions::default();
+    let image = Image::new(1, 1, vec![0xff0000ff]);
+    assert!(matches!(image.scale(factor, &options), Err(ScaleFxError::OutputTooLarge { width: 1, height: 1 })));
+    assert!(matches!(image.scale(factor - 1, &options), Err(ScaleFxError::OutputTooLarge { width: 1, height: 1 })), "Derived from it");
+}
+
+#[test]
+fn derived_factors_only_use_the_source_colours() {
+    let image = load("Bip");
+    for factor in [2, 4, 5, 6, 7, 8] {
+        let scaled = image.scale(factor, &ScaleFxOptions::default()).unwrap();
+        assert_eq!((scaled.width, scaled.height), (image.width * factor, image.height * factor));
+        // Besides the transparent border smoothed in around the edges.
+        assert!(scaled.pixels.iter().all(|pixel| *pixel == 0 || image.pixels.contains(pixel)), "{}x", factor);
+    }
+}
+
+#[test]
+fn derived_factors_keep_flat_areas_flat() {
+    // A solid square's middle is left alone by ScaleFX, so it must stay solid however it's shrunk.
+    let red = 0xff0000ff;
+    let image = Image::new(8, 8, (0..64).map(|i| if (2..6).contains(&(i % 8)) && (2..6).contains(&(i / 8)) { red } else { 0 }).collect());
+    for factor in [2, 4, 6] {
+        let scaled = image.scale(factor, &ScaleFxOptions::default()).unwrap();
+        for y in 3 * factor..5 * factor {
+            assert!(scaled.pixels[y * scaled.width + 3 * factor..y * scaled.width + 5 * factor].iter().all(|&pixel| pixel == red), "{}x", factor);
+        }
+        assert_eq!(scaled.pixels[0], 0);
+    }
+}
+
+#[test]
+fn derived_factors_shrink_by_majority() {
+    // 6x from 9x: every other output pixel covers all of one 9x pixel, half of the ones to its right and below,
+    // and a quarter of the one diagonally, so takes whichever colour has the most of that.
+    let image = load("Slug");
+    let options = ScaleFxOptions::default();
+    let native = image.scale9x(&options).unwrap();
+    let scaled = image.scale(6, &options).unwrap();
+    for y in (0..scaled.height).step_by(2) {
+        for x in (0..scaled.width).step_by(2) {
+            let (left, top) = (x * 3 / 2, y * 3 / 2);
+            let overlaps = [(0, 0, 4), (1, 0, 2), (0, 1, 2), (1, 1, 1)].map(|(dx, dy, area)| (native.pixels[(top + dy) * native.width + left + dx], area));
+            let coverage = |colour: u32| overlaps.iter().filter(|&&(overlapped, _)| overlapped == colour).map(|&(_, area)| area).sum::<usize>();
+            let pixel = scaled.pixels[y * scaled.width + x];
+            assert!(overlaps.iter().all(|&(colour, _)| coverage(pixel) >= coverage(colour)), "{},{}", x, y);
+        }
+    }
+}
+
+#[test]
+fn sheets_keep_their_cells_lined_up() {
+    // Two sprites side by side, scaled as a sheet, should each match scaling that sprite alone.
+    let (a, b) = (load("Keen"), load("Bip"));
+    let (width, height) = (a.width.max(b.width), a.height.max(b.height));
+    let cell = |image: &Image| Image::new(width, height, (0..width * height).map(|i| {
+        let (x, y) = (i % width, i / width);
+        if x < image.width && y < image.height { image.pixels[y * image.width + x] } else { 0 }
+    }).collect());
+    let (a, b) = (cell(&a), cell(&b));
+    let sheet = Image::new(width * 2, height, (0..height).flat_map(|y| a.pixels[y * width..(y + 1) * width].iter().chain(&b.pixels[y * width..(y + 1) * width]).copied().collect::<Vec<_>>()).collect());
+    let options = ScaleFxOptions::default();
+    for factor in [2, 3, 4] {
+        let scaled = scale_sheet(&sheet, &SheetLayout::new(width, height), factor, &options).unwrap();
+        let (a, b) = (a.scale(factor, &options).unwrap(), b.scale(factor, &options).unwrap());
+        let row = |y: usize| a.pixels[y * a.width..(y + 1) * a.width].iter().chain(&b.pixels[y * b.width..(y + 1) * b.width]).copied().collect::<Vec<_>>();
+        assert_eq!(scaled.pixels, (0..a.height).flat_map(row).collect::<Vec<_>>(), "{}x", factor);
+    }
+}
+
+#[test]
+fn indexed_matches_scaling_colours() {
+    let path = "readme/Mine.png";
+    let palette = png_io::load_png_palette(path).unwrap().unwrap().colours();
+    let image = png_io::load_png(path).unwrap();
+    let indices = image.pixels.iter().map(|pixel| palette.iter().position(|colour| colour == pixel).unwrap() as u8).collect();
+    let indexed = IndexedImage::new(image.width, image.height, indices, palette);
+    // Fully transparent pixels may be any colour in the palette with no alpha.
+    let without_transparent_colours = |image: &Image| image.pixels.iter().map(|&pixel| if pixel & 0xff < 0x80 { 0 } else { pixel }).collect::<Vec<_>>();
+    for factor in [1, 2, 4, 9] {
+        let scaled = indexed.scale(factor, &ScaleFxOptions::default()).unwrap().to_image();
+        assert_eq!(without_transparent_colours(&scaled), without_transparent_colours(&image.scale(factor, &ScaleFxOptions::default()).unwrap()), "{}x", factor);
+    }
+}
@@ -0,0 +1,101 @@
+// Checks animated GIFs are composited, scaled and re-encoded with their timings.
+
+mod common;
+
+use common::{BLUE, GREEN, RED, temp_path};
+use gif::{DisposalMethod, Repeat};
+use scalefx_rs::{Animation, AnimationFrame, Image, ScaleFxOptions, gif_io};
+
+// Writes a 4x4 GIF whose frames only cover part of the canvas, to exercise each disposal method.
+fn write_partial_frames_gif(path: &str) {
+    let palette = [0xff, 0, 0, 0, 0xff, 0, 0, 0, 0xff, 0, 0, 0]; // Red, green, blue, and a transparent entry.
+    let file = std::fs::File::create(path).unwrap();
+    let mut encoder = gif::Encoder::new(file, 4, 4, &palette).unwrap();
+    encoder.set_repeat(Repeat::Finite(2)).unwrap();
+    let frame = |left, top, width, height, pixels: &[u8], dispose, delay| gif::Frame {
+        left, top, width, height, dispose, delay,
+        transparent: Some(3),
+        buffer: pixels.to_vec().into(),
+        ..gif::Frame::default()
+    };
+    // A red background, which stays.
+    encoder.write_frame(&frame(0, 0, 4, 4, &[0; 16], DisposalMethod::Keep, 10)).unwrap();
+    // A green 2x2 in the top-left with a transparent hole, cleared back to transparent afterwards.
+    encoder.write_frame(&frame(0, 0, 2, 2, &[1, 1, 1, 3], DisposalMethod::Background, 20)).unwrap();
+    // A blue 2x1 in the bottom-right, undone afterwards.
+    encoder.write_frame(&frame(2, 3, 2, 1, &[2, 2], DisposalMethod::Previous, 30)).unwrap();
+    // Nothing new, to show the state after the previous frame's disposal.
+    encoder.write_frame(&frame(0, 0, 1, 1, &[3], DisposalMethod::Keep, 40)).unwrap();
+}
+
+#[test]
+fn frames_are_composited_by_disposal_method() {
+    let path = temp_path("partial_frames.gif");
+    write_partial_frames_gif(&path);
+    let animation = gif_io::load_gif(&path).unwrap();
+    let frames: Vec<&Vec<u32>> = animation.frames.iter().map(|frame| &frame.image.pixels).collect();
+    let mut expected = vec![RED; 16];
+    assert_eq!(frames[0], &expected);
+    expected[0] = GREEN; expected[1] = GREEN; expected[4] = GREEN; // The hole shows the red underneath.
+    assert_eq!(frames[1], &expected);
+    expected[0] = 0; expected[1] = 0; expected[4] = 0; expected[5] = 0; // Background disposal clears the frame's area.
+    expected[14] = BLUE; expected[15] = BLUE;
+    assert_eq!(frames[2], &expected);
+    expected[14] = RED; expected[15] = RED; // Previous disposal restores what was there.
+    assert_eq!(frames[3], &expected);
+    let delays: Vec<(u16, u16)> = animation.frames.iter().map(|frame| (frame.delay_numerator, frame.delay_denominator)).collect();
+    assert_eq!(delays, [(10, 100), (20, 100), (30, 100), (40, 100)]);
+    assert_eq!(animation.plays, 3);
+}
+
+#[test]
+fn scaled_gif_keeps_timings_and_colours() {
+    let in_path = temp_path("scale_in.gif");
+    let out_path = temp_path("scale_out.gif");
+    write_partial_frames_gif(&in_path);
+    let animation = gif_io::load_gif(&in_path).unwrap();
+    let scaled = animation.scale3x(&ScaleFxOptions::default()).unwrap();
+    gif_io::save_gif(&scaled, &out_path).unwrap();
+    let reloaded = gif_io::load_gif(&out_path).unwrap();
+    assert_eq!(reloaded, scaled);
+    for (frame, original) in reloaded.frames.iter().zip(&animation.frames) {
+        assert_eq!((frame.image.width, frame.image.height), (12, 12));
+        // Besides the transparency smoothed in from the border, every colour comes from the original frame.
+        assert!(frame.image.pixels.iter().all(|pixel| *pixel == 0 || original.image.pixels.contains(pixel)), "ScaleFX shouldn't add colours");
+    }
+}
+
+#[test]
+fn loop_counts_round_trip() {
+    let path = temp_path("loops.gif");
+    for plays in [0, 1, 2, 7] {
+        let frame = AnimationFrame { image: Image::new(2, 1, vec![RED, 0]), delay_numerator: 5, delay_denominator: 100 };
+        let animation = Animation { frames: vec![frame.clone(), AnimationFrame { delay_numerator: 7, ..frame }], plays };
+        gif_io::save_gif(&animation, &path).unwrap();
+        assert_eq!(gif_io::load_gif(&path).unwrap(), animation);
+    }
+}
+
+#[test]
+fn too_many_colours_overall_uses_local_palettes() {
+    let path = temp_path("local_palettes.gif");
+    // 200 colours per frame, 400 overall.
+    let frame = |offset: u32| AnimationFrame {
+        image: Image::new(20, 10, (0..200).map(|i| ((i + offset) << 8) | 0xff).collect()),
+        delay_numerator: 1,
+        delay_denominator: 10,
+    };
+    let animation = Animation { frames: vec![frame(0), frame(200)], plays: 0 };
+    gif_io::save_gif(&animation, &path).unwrap();
+    let reloaded = gif_io::load_gif(&path).unwrap();
+    assert_eq!(reloaded.frames[0].image, animation.frames[0].image);
+    assert_eq!(reloaded.frames[1].image, animation.frames[1].image);
+    assert_eq!(reloaded.frames[0].delay_numerator, 10); // Converted to hundredths.
+}
+
+#[test]
+fn too_many_colours_in_a_frame_is_an_error() {
+    let image = Image::new(300, 1, (0..300).map(|i| (i << 8) | 0xff).collect());
+    let animation = Animation { frames: vec![AnimationFrame { image, delay_numerator: 0, delay_denominator: 100 }], plays: 1 };
+    assert!(gif_io::save_gif(&animation, &temp_path("too_many.gif")).is_err());
+}
@@ -0,0 +1,71 @@
+// Golden-image regression tests: scales every sample sprite in the readme folder and compares
+// the output pixel-exactly against the committed results in tests/golden.
+// If a change to the passes is meant to alter the output, regenerate the goldens with:
+// SCALEFX_BLESS=1 cargo test --test golden
+// then check the new images over before committing them.
+
+mod common;
+
+use common::sprites;
+use scalefx_rs::{Image, ScaleFxOptions, png_io};
+
+const GOLDEN_DIR: &str = "tests/golden";
+
+fn is_blessing() -> bool {
+    std::env::var_os("SCALEFX_BLESS").is_some_and(|bless| bless != "0")
+}
+
+// Highlights differing pixels in red over a faded copy of the expected image, so it's easy to see where they are.
+fn diff_image(actual: &Image, expected: &Image) -> Image {
+    let pixels = actual.pixels.iter().zip(&expected.pixels).map(|(&actual, &expected)| {
+        if actual != expected { return 0xff0000ff }
+        let fade = |channel: u32| (channel + 0xff * 3) / 4; // Three quarters of the way to white.
+        fade(expected >> 24) << 24 | fade((expected >> 16) & 0xff) << 16 | fade((expected >> 8) & 0xff) << 8 | 0xff
+    }).collect();
+    Image::new(actual.width, actual.height, pixels)
+}
+
+// Compares the output against its golden, returning a description of the problem if it doesn't match.
+// When blessing, the golden is overwritten instead.
+fn check_golden(name: &str, actual: &Image) -> Result<(), String> {
+    let golden_path = format!("{}/{}.png", GOLDEN_DIR, name);
+    if is_blessing() {
+        std::fs::create_dir_all(GOLDEN_DIR).unwrap();
+        png_io::save_png(actual, &golden_path).unwrap();
+        return Ok(());
+    }
+    let expected = png_io::load_png(&golden_path).map_err(|error| format!("{}: couldn't load {}: {}", name, golden_path, error))?;
+    if (actual.width, actual.height) != (expected.width, expected.height) {
+        return Err(format!("{}: expected {}x{}, but got {}x{}", name, expected.width, expected.height, actual.width, actual.height));
+    }
+    let differences = actual.pixels.iter().zip(&expected.pixels).filter(|(actual, expected)| actual != expected).count();
+    if differences == 0 { return Ok(()) }
+    let diff_dir = format!("{}/golden-diffs", env!("CARGO_TARGET_TMPDIR"));
+    std::fs::create_dir_all(&diff_dir).unwrap();
+    let diff_path = format!("{}/{}.png", diff_dir, name);
+    png_io::save_png(&diff_image(actual, &expected), &diff_path).unwrap();
+    Err(format!("{}: {} of {} pixels differ, see {}", name, differences, actual.pixels.len(), diff_path))
+}
+
+// Scales every fixture and checks them all, so one failure doesn't hide the others.
+fn check_all(suffix: &str, scale: impl Fn(&Image) -> Image) {
+    let failures: Vec<String> = sprites().iter()
+        .filter_map(|(name, image)| check_golden(&format!("{}.{}", name, suffix), &scale(image)).err())
+        .collect();
+    assert!(failures.is_empty(), "{} golden images differ:\n{}", failures.len(), failures.join("\n"));
+}
+
+#[test]
+fn scale3x_matches_goldens() {
+    check_all("3x", |image| image.scale3x(&ScaleFxOptions::default()).unwrap());
+}
+
+#[test]
+fn scale9x_matches_goldens() {
+    check_all("9x", |image| image.scale9x(&ScaleFxOptions::default()).unwrap());
+}
+
+#[test]
+fn hybrid_matches_goldens() {
+    check_all("3x.hybrid", |image| image.scale3x(&ScaleFxOptions::default().with_hybrid(true)).unwrap());
+}
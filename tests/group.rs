@@ -0,0 +1,63 @@
+// Checks scaling a group of images gives them all the same shape.
+
+mod common;
+
+use common::{load, recolour, rotate_channels};
+use scalefx_rs::{BorderMode, Image, ScaleFxError, ScaleFxOptions, TagMap, scale_group};
+
+// Every colour as the same grey, bar transparency, so only the sprite's outline has edges.
+fn silhouette(colour: u32) -> u32 {
+    0x808080ff | (colour & 0xff)
+}
+
+#[test]
+fn groups_of_one_match_scaling() {
+    let image = load("BloogletR");
+    let options = ScaleFxOptions::default();
+    for factor in [2, 3, 9] {
+        assert_eq!(scale_group(std::slice::from_ref(&image), factor, &options).unwrap(), [image.scale(factor, &options).unwrap()], "{}x", factor);
+        // The largest of an image's distances and its own are the same, so doubling it up changes nothing.
+        assert_eq!(scale_group(&[image.clone(), image.clone()], factor, &options).unwrap()[1], image.scale(factor, &options).unwrap(), "{}x", factor);
+    }
+}
+
+#[test]
+fn palette_variants_get_the_same_shape() {
+    let original = load("BloogletR");
+    let options = ScaleFxOptions::default().with_border_mode(BorderMode::Clamp);
+    let variant = recolour(&original, rotate_channels);
+    assert_ne!(variant.scale9x(&options).unwrap(), recolour(&original.scale9x(&options).unwrap(), rotate_channels), "Scaled on their own, they differ in shape");
+    let [original, variant]: [Image; 2] = scale_group(&[original, variant], 9, &options).unwrap().try_into().unwrap();
+    assert_eq!(variant, recolour(&original, rotate_channels));
+}
+
+#[test]
+fn silhouettes_take_the_sprites_shape() {
+    // A silhouette's only edges are around the outside, which the sprite has too, so grouping them leaves the sprite
+    // as it was alone, and the silhouette takes on the sprite's shape.
+    let original = load("BloogletR");
+    let options = ScaleFxOptions::default();
+    let grouped = scale_group(&[original.clone(), recolour(&original, silhouette)], 3, &options).unwrap();
+    assert_eq!(grouped[0], original.scale3x(&options).unwrap());
+    assert_eq!(grouped[1], recolour(&grouped[0], silhouette));
+}
+
+#[test]
+fn shared_tags_match_the_group() {
+    let images = [load("BloogletR"), recolour(&load("BloogletR"), rotate_channels)];
+    let options = ScaleFxOptions::default();
+    let tags = TagMap::for_group(&images, 4, &options).unwrap();
+    let scaled = scale_group(&images, 4, &options).unwrap();
+    for (image, scaled) in images.iter().zip(&scaled) {
+        assert_eq!(&tags.apply(image, 4, &options).unwrap(), scaled);
+    }
+}
+
+#[test]
+fn invalid_groups_are_an_error() {
+    let options = ScaleFxOptions::default();
+    assert!(matches!(scale_group(&[], 3, &options), Err(ScaleFxError::EmptyGroup)));
+    let mismatch = scale_group(&[load("BloogletR"), load("BloogletG")], 3, &options);
+    assert!(matches!(mismatch, Err(ScaleFxError::GroupSizeMismatch { .. })), "Different sizes");
+    assert!(scale_group(&[load("BloogletR")], 0, &options).is_err());
+}
@@ -0,0 +1,100 @@
+// Checks the hybrid mode's reverse anti-aliasing only changes anti-aliased ramps.
+
+mod common;
+
+use common::{load, sprites};
+use scalefx_rs::{BorderMode, Image, IndexedImage, ScaleFxError, ScaleFxOptions};
+
+const BLACK: u32 = 0x000000ff;
+const GREY: u32 = 0x808080ff;
+const WHITE: u32 = 0xffffffff;
+
+fn hybrid() -> ScaleFxOptions {
+    ScaleFxOptions::default().with_hybrid(true)
+}
+
+// Whether the pixel's colour differs from both of its neighbours along a row or column, ie it's part way along a
+// ramp between them. Transparent neighbours and those past the edge don't count, as they don't to the hybrid pass.
+fn is_between_neighbours(image: &Image, x: usize, y: usize) -> bool {
+    let at = |dx: isize, dy: isize| {
+        let x = x.checked_add_signed(dx).filter(|&x| x < image.width)?;
+        let y = y.checked_add_signed(dy).filter(|&y| y < image.height)?;
+        Some(image.pixels[y * image.width + x]).filter(|colour| colour & 0xff >= 0x80)
+    };
+    let e = image.pixels[y * image.width + x];
+    let differs = |neighbour: Option<u32>| neighbour.is_some_and(|neighbour| neighbour != e);
+    (differs(at(-1, 0)) && differs(at(1, 0))) || (differs(at(0, -1)) && differs(at(0, 1)))
+}
+
+#[test]
+fn hard_edges_are_unchanged() {
+    // Flat areas, hard edges, a one pixel line and a checkerboard have no ramps to sharpen.
+    let rows = [
+        "........",
+        ".BBBBBB.",
+        ".BWWWWB.",
+        ".BWBWBB.",
+        ".BWWWWB.",
+        ".BBBBBB.",
+        ".BWBWBW.",
+        ".WBWBWB.",
+    ];
+    let pixels = rows.iter().flat_map(|row| row.chars()).map(|c| match c {
+        'B' => BLACK,
+        'W' => WHITE,
+        _ => 0,
+    }).collect();
+    let image = Image::new(8, 8, pixels);
+    assert_eq!(image.scale3x(&hybrid()).unwrap(), image.scale3x(&ScaleFxOptions::default()).unwrap());
+}
+
+#[test]
+fn ramps_are_steepened() {
+    // A black to white ramp with a grey step in the middle, as anti-aliasing would leave between two colours.
+    let image = Image::new(5, 3, [BLACK, BLACK, GREY, WHITE, WHITE].repeat(3));
+    let plain = image.scale3x(&ScaleFxOptions::default().with_border_mode(BorderMode::Clamp)).unwrap();
+    let scaled = image.scale3x(&hybrid().with_border_mode(BorderMode::Clamp)).unwrap();
+    let grey_row = |image: &Image| image.pixels[4 * image.width + 6..4 * image.width + 9].to_vec();
+    assert_eq!(grey_row(&plain), [GREY; 3]);
+    // The grey pixel is darker on its black side and lighter on its white side, and grey in the middle.
+    let [left, middle, right] = grey_row(&scaled).try_into().unwrap();
+    assert_eq!(middle, GREY);
+    assert!(left >> 24 < 0x80 && right >> 24 > 0x80, "{:08x} {:08x}", left, right);
+    assert!([left, right].iter().all(|colour| colour >> 24 == (colour >> 16) & 0xff && colour & 0xff == 0xff), "Still an opaque grey");
+    // The black and white either side are flat, so only the grey column changes.
+    let mut changed_columns = (0..plain.pixels.len()).filter(|&i| plain.pixels[i] != scaled.pixels[i]).map(|i| i % plain.width / 3);
+    assert!(changed_columns.all(|x| x == 2));
+}
+
+#[test]
+fn sprites_only_differ_on_ramps() {
+    let mut differing_sprites = 0;
+    for (name, image) in sprites() {
+        let plain = image.scale3x(&ScaleFxOptions::default()).unwrap();
+        let scaled = image.scale3x(&hybrid()).unwrap();
+        let mut differences = 0;
+        for (i, (&plain, &scaled)) in plain.pixels.iter().zip(&scaled.pixels).enumerate() {
+            if plain == scaled { continue }
+            let (x, y) = ((i % (image.width * 3)) / 3, i / (image.width * 3) / 3);
+            assert!(is_between_neighbours(&image, x, y), "{}: {},{} isn't on a ramp", name, x, y);
+            assert_eq!(scaled & 0xff, plain & 0xff, "{}: alpha is kept", name);
+            differences += 1;
+        }
+        if differences > 0 { differing_sprites += 1 }
+    }
+    assert!(differing_sprites > 0, "Some of the sprites are anti-aliased");
+}
+
+#[test]
+fn threads_and_bands_match() {
+    let image = load("RoboRed");
+    let expected = image.scale3x(&hybrid()).unwrap();
+    assert_eq!(image.scale3x(&hybrid().with_threads(3)).unwrap(), expected);
+    assert_eq!(image.scale3x(&hybrid().with_band_rows(5)).unwrap(), expected);
+}
+
+#[test]
+fn palette_indices_cant_be_blended() {
+    let image = IndexedImage::new(2, 1, vec![0, 1], vec![BLACK, WHITE]);
+    assert!(matches!(image.scale3x(&hybrid()), Err(ScaleFxError::InvalidOptions(_))));
+}
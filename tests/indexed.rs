@@ -0,0 +1,80 @@
+// Checks scaling palette indices matches scaling their colours.
+
+mod common;
+
+use common::without_transparent_colours;
+use scalefx_rs::{BorderMode, IndexedImage, ScaleFxError, ScaleFxOptions, png_io};
+
+// Loads one of the palettised readme sprites as indices into its palette.
+fn load_indexed(name: &str) -> IndexedImage {
+    let path = format!("readme/{}.png", name);
+    let palette = png_io::load_png_palette(&path).unwrap().unwrap().colours();
+    let image = png_io::load_png(&path).unwrap();
+    let indices = image.pixels.iter().map(|pixel| palette.iter().position(|colour| colour == pixel).unwrap() as u8).collect();
+    IndexedImage::new(image.width, image.height, indices, palette)
+}
+
+#[test]
+fn matches_scaling_colours() {
+    for name in ["Keen", "Bip", "Mine", "Ampton"] {
+        let indexed = load_indexed(name);
+        for options in [ScaleFxOptions::default(), ScaleFxOptions::default().with_border_mode(BorderMode::Clamp).with_band_rows(4)] {
+            let expected = indexed.to_image().scale9x(&options).unwrap();
+            let scaled = indexed.scale9x(&options).unwrap();
+            assert_eq!(scaled.palette, indexed.palette);
+            assert_eq!(without_transparent_colours(&scaled.to_image()), without_transparent_colours(&expected), "{}", name);
+        }
+    }
+}
+
+#[test]
+fn duplicate_colours_keep_their_indices() {
+    // Entries 1 and 2 are the same red, as if 2 were about to be cycled to another colour.
+    let palette = vec![0, 0xff0000ff, 0xff0000ff, 0x0000ffff];
+    let indices = vec![
+        1, 1, 3, 3,
+        1, 2, 3, 3,
+        2, 2, 3, 3,
+    ];
+    let image = IndexedImage::new(4, 3, indices, palette);
+    let scaled = image.scale3x(&ScaleFxOptions::default()).unwrap();
+    assert_eq!(scaled.to_image(), image.to_image().scale3x(&ScaleFxOptions::default()).unwrap());
+    // Each subpixel is copied from a source pixel, so the reds keep which entry they were.
+    // The centre subpixel is always the source pixel itself.
+    let centre = |x: usize, y: usize| scaled.indices[(y * 3 + 1) * scaled.width + x * 3 + 1];
+    assert_eq!([centre(0, 1), centre(1, 1), centre(0, 2)], [1, 2, 2]);
+}
+
+#[test]
+fn scale9x_matches_scale3x_twice() {
+    let indexed = load_indexed("Slug");
+    let options = ScaleFxOptions::default();
+    assert_eq!(indexed.scale9x(&options).unwrap(), indexed.scale3x(&options).unwrap().scale3x(&options).unwrap());
+}
+
+#[test]
+fn indices_past_the_palette_are_an_error() {
+    let image = IndexedImage::new(2, 1, vec![0, 2], vec![0, 0xff0000ff]);
+    assert!(matches!(image.scale3x(&ScaleFxOptions::default()), Err(ScaleFxError::IndexOutOfRange { index: 2, palette_len: 2 })));
+}
+
+#[test]
+fn transparent_border_needs_a_transparent_entry() {
+    let image = IndexedImage::new(2, 2, vec![0, 1, 1, 0], vec![0xff0000ff, 0x0000ffff]);
+    assert!(matches!(image.scale3x(&ScaleFxOptions::default()), Err(ScaleFxError::InvalidOptions(_))));
+    let clamped = image.scale3x(&ScaleFxOptions::default().with_border_mode(BorderMode::Clamp)).unwrap();
+    assert_eq!(clamped.to_image(), image.to_image().scale3x(&ScaleFxOptions::default().with_border_mode(BorderMode::Clamp)).unwrap());
+}
+
+#[test]
+fn entries_past_256_are_ignored() {
+    let indexed = load_indexed("Keen");
+    let mut palette = indexed.palette.clone();
+    palette.resize(256, 0x00ff00ff);
+    let full = IndexedImage::new(indexed.width, indexed.height, indexed.indices.clone(), palette.clone());
+    palette.resize(300, 0xff00ffff); // Out of a u8 index's reach.
+    let oversized = IndexedImage::new(indexed.width, indexed.height, indexed.indices.clone(), palette.clone());
+    let scaled = oversized.scale3x(&ScaleFxOptions::default()).unwrap();
+    assert_eq!(scaled.palette, palette);
+    assert_eq!(scaled.indices, full.scale3x(&ScaleFxOptions::default()).unwrap().indices);
+}
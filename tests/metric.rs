@@ -0,0 +1,85 @@
+// Checks every colour metric is scaled to the range the threshold expects.
+
+use scalefx_rs::{ColourMetric, IndexedImage, ScaleFxOptions, png_io};
+
+const METRICS: [ColourMetric; 7] = [
+    ColourMetric::Redmean,
+    ColourMetric::Shader,
+    ColourMetric::Euclidean,
+    ColourMetric::YCbCr,
+    ColourMetric::Lab76,
+    ColourMetric::Lab2000,
+    ColourMetric::Oklab,
+];
+
+// A spread of opaque colours, from a simple xorshift so the test is repeatable.
+fn colours() -> Vec<u32> {
+    let mut state = 0x2545f491u32;
+    let mut colours = vec![0x000000ff, 0xffffffff, 0xff0000ff, 0x00ff00ff, 0x0000ffff, 0x808080ff];
+    for _ in 0..200 {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        colours.push(state | 0xff);
+    }
+    colours
+}
+
+#[test]
+fn black_vs_white_is_one() {
+    for metric in METRICS {
+        let distance = metric.distance(0x000000ff, 0xffffffff);
+        assert!((distance - 1.).abs() < 0.001, "{:?} gave {}", metric, distance);
+    }
+}
+
+#[test]
+fn distances_are_between_zero_and_one() {
+    let colours = colours();
+    for metric in METRICS {
+        for &a in &colours {
+            assert_eq!(metric.distance(a, a), 0., "{:?}", metric);
+            for &b in &colours {
+                let distance = metric.distance(a, b);
+                assert!((0. ..=1.).contains(&distance), "{:?} gave {} for {:08x} vs {:08x}", metric, distance, a, b);
+                assert!((distance - metric.distance(b, a)).abs() < 1e-5, "{:?} isn't symmetric for {:08x} vs {:08x}", metric, a, b);
+                if a != b { assert!(distance > 0., "{:?} can't tell {:08x} from {:08x}", metric, a, b) }
+            }
+        }
+    }
+}
+
+#[test]
+fn transparency_is_binary() {
+    for metric in METRICS {
+        assert_eq!(metric.distance(0xff000000, 0x00ff0010), 0., "Transparent pixels are all the same");
+        assert_eq!(metric.distance(0xff000000, 0xff0000ff), 1., "Transparent vs opaque is as different as can be");
+        assert_eq!(metric.distance(0xff000080, 0xff0000ff), 0., "Alpha doesn't matter between opaque pixels");
+    }
+}
+
+#[test]
+fn perceptual_metrics_weight_green_over_blue() {
+    // Eyes are far more sensitive to changes in green than blue; plain RGB can't tell them apart.
+    let grey = 0x808080ff;
+    for metric in [ColourMetric::Redmean, ColourMetric::Shader, ColourMetric::YCbCr, ColourMetric::Lab76, ColourMetric::Lab2000, ColourMetric::Oklab] {
+        assert!(metric.distance(grey, 0x80a080ff) > metric.distance(grey, 0x8080a0ff), "{:?}", metric);
+    }
+    assert_eq!(ColourMetric::Euclidean.distance(grey, 0x80a080ff), ColourMetric::Euclidean.distance(grey, 0x8080a0ff));
+}
+
+#[test]
+fn every_metric_scales() {
+    let image = png_io::load_png("readme/Keen.png").unwrap();
+    let palette = png_io::load_png_palette("readme/Keen.png").unwrap().unwrap().colours();
+    let indices = image.pixels.iter().map(|pixel| palette.iter().position(|colour| colour == pixel).unwrap() as u8).collect();
+    let indexed = IndexedImage::new(image.width, image.height, indices, palette);
+    for metric in METRICS {
+        let options = ScaleFxOptions::default().with_colour_metric(metric);
+        let scaled = image.scale3x(&options).unwrap();
+        assert_eq!(scaled, image.scale3x(&options.with_simd(false)).unwrap(), "{:?}", metric);
+        // The palette's distance table uses the same metric.
+        let from_indices = indexed.scale3x(&options).unwrap().to_image();
+        assert!(from_indices.pixels.iter().zip(&scaled.pixels).all(|(a, b)| a == b || (a & 0xff == 0 && b & 0xff == 0)), "{:?}", metric);
+    }
+}
@@ -0,0 +1,43 @@
+// Checks the options reject values outside the ranges the shader allows.
+
+use scalefx_rs::{ScaleFxError, ScaleFxOptions, scale3x_with_options, try_scale3x};
+
+const PIXELS: [u32; 2] = [0xff0000ff, 0x0000ffff];
+
+// Options with the threshold as given, which with_threshold would clamp.
+fn unclamped(threshold: f32) -> ScaleFxOptions {
+    let mut options = ScaleFxOptions::default();
+    options.threshold = threshold;
+    options
+}
+
+#[test]
+fn with_threshold_clamps_to_the_shaders_range() {
+    let options = ScaleFxOptions::default();
+    assert_eq!(options.with_threshold(0.3).threshold, 0.3);
+    assert_eq!(options.with_threshold(0.).threshold, ScaleFxOptions::MIN_THRESHOLD);
+    assert_eq!(options.with_threshold(-1.).threshold, ScaleFxOptions::MIN_THRESHOLD);
+    assert_eq!(options.with_threshold(2.).threshold, ScaleFxOptions::MAX_THRESHOLD);
+    assert_eq!(options.with_threshold(f32::INFINITY).threshold, ScaleFxOptions::MAX_THRESHOLD);
+    assert_eq!(options.with_threshold(f32::NAN).threshold, ScaleFxOptions::default().threshold);
+    assert!(options.with_threshold(f32::NAN).is_valid());
+}
+
+#[test]
+fn out_of_range_thresholds_are_invalid() {
+    for threshold in [0., 0.009, 1.01, -0.5, f32::NAN, f32::INFINITY] {
+        let options = unclamped(threshold);
+        assert!(!options.is_valid(), "{}", threshold);
+        assert!(matches!(options.validate(), Err(ScaleFxError::InvalidOptions(_))), "{}", threshold);
+        assert!(matches!(try_scale3x(2, 1, &PIXELS, &options), Err(ScaleFxError::InvalidOptions(_))), "{}", threshold);
+    }
+    for threshold in [ScaleFxOptions::MIN_THRESHOLD, 0.5, ScaleFxOptions::MAX_THRESHOLD] {
+        assert!(unclamped(threshold).is_valid(), "{}", threshold);
+    }
+}
+
+#[test]
+#[should_panic(expected = "threshold")]
+fn scaling_with_invalid_options_panics() {
+    scale3x_with_options(2, 1, &PIXELS, &unclamped(2.));
+}
@@ -0,0 +1,62 @@
+// Checks palettised PNGs can be saved with their original palette.
+
+mod common;
+
+use common::{temp_path, without_transparent_colours};
+use scalefx_rs::{Image, ScaleFxOptions, png_io};
+use scalefx_rs::png_io::PngPalette;
+
+#[test]
+fn scaled_sprite_keeps_its_palette() {
+    for name in ["Keen", "Bip", "Ampton"] {
+        let in_path = format!("readme/{}.png", name);
+        let palette = png_io::load_png_palette(&in_path).unwrap().expect("The readme sprites are palettised");
+        let scaled = png_io::load_png(&in_path).unwrap().scale3x(&ScaleFxOptions::default()).unwrap();
+        let out_path = temp_path(&format!("{}.3x.png", name));
+        png_io::save_png_with_palette(&scaled, &palette, &out_path).unwrap();
+        assert_eq!(png_io::load_png_palette(&out_path).unwrap(), Some(palette), "{}", name);
+        assert_eq!(without_transparent_colours(&png_io::load_png(&out_path).unwrap()), without_transparent_colours(&scaled), "{}", name);
+    }
+}
+
+#[test]
+fn indices_are_packed_at_small_depths() {
+    // Widths that don't fill the last byte of each row, for 1, 2, 4 and 8 bit indices.
+    for entries in [2, 3, 16, 17] {
+        let palette = PngPalette { rgb: (0..entries * 3).map(|i| i as u8).collect(), alphas: vec![0, 0x80] };
+        let colours = palette.colours();
+        let image = Image::new(5, 3, (0..15).map(|i| colours[(i * 7) % entries]).collect());
+        let path = temp_path(&format!("packed_{}.png", entries));
+        png_io::save_png_with_palette(&image, &palette, &path).unwrap();
+        assert_eq!(png_io::load_png_palette(&path).unwrap(), Some(palette));
+        assert_eq!(png_io::load_png(&path).unwrap(), image);
+    }
+}
+
+#[test]
+fn transparency_uses_the_transparent_entry() {
+    let palette = PngPalette { rgb: vec![0xff, 0, 0, 0xff, 0, 0xff, 0, 0, 0xff], alphas: vec![0xff, 0] };
+    let image = Image::new(3, 1, vec![0xff0000ff, 0, 0x0000ffff]);
+    let path = temp_path("transparent_entry.png");
+    png_io::save_png_with_palette(&image, &palette, &path).unwrap();
+    assert!(png_io::load_png_palette(&path).unwrap().is_some());
+    assert_eq!(png_io::load_png(&path).unwrap().pixels, [0xff0000ff, 0xff00ff00, 0x0000ffff]);
+}
+
+#[test]
+fn colours_outside_the_palette_fall_back_to_rgba() {
+    let palette = PngPalette { rgb: vec![0xff, 0, 0], alphas: vec![] };
+    // There's no transparent entry for the transparency, either.
+    for pixels in [vec![0xff0000ff, 0x00ff00ff], vec![0xff0000ff, 0]] {
+        let image = Image::new(2, 1, pixels);
+        let path = temp_path("fallback.png");
+        png_io::save_png_with_palette(&image, &palette, &path).unwrap();
+        assert_eq!(png_io::load_png_palette(&path).unwrap(), None);
+        assert_eq!(png_io::load_png(&path).unwrap(), image);
+    }
+}
+
+#[test]
+fn rgba_pngs_have_no_palette() {
+    assert_eq!(png_io::load_png_palette("readme/RoboRed.png").unwrap(), None);
+}
@@ -0,0 +1,29 @@
+// Checks the readme's scaled examples, which were made before the passes were packed and fused, still come out
+// exactly the same, so those optimisations didn't change the output.
+
+use scalefx_rs::{Image, ScaleFxOptions, png_io};
+
+// These examples were made by an older version of ScaleFX-rs, or cropped afterwards.
+const OUTDATED: [&str; 2] = ["RoboRed", "sq3.fixed"];
+
+// The pixels with every fully transparent one as 0, as PNG encoders may keep any colour under transparency.
+fn visible_pixels(image: &Image) -> Vec<u32> {
+    image.pixels.iter().map(|&pixel| if pixel & 0xff == 0 { 0 } else { pixel }).collect()
+}
+
+#[test]
+fn readme_examples_are_unchanged() {
+    let mut names: Vec<String> = std::fs::read_dir("readme").unwrap()
+        .map(|entry| entry.unwrap().file_name().to_str().unwrap().to_string())
+        .filter_map(|name| Some(name.strip_suffix(".big.png")?.to_string()))
+        .filter(|name| !OUTDATED.contains(&name.as_str()))
+        .collect();
+    names.sort();
+    assert!(names.len() > 50);
+    for name in names {
+        let expected = png_io::load_png(&format!("readme/{}.big.png", name)).unwrap();
+        let scaled = png_io::load_png(&format!("readme/{}.png", name)).unwrap().scale9x(&ScaleFxOptions::default()).unwrap();
+        assert_eq!((scaled.width, scaled.height), (expected.width, expected.height), "{}", name);
+        assert!(visible_pixels(&scaled) == visible_pixels(&expected), "{} differs from its readme example", name);
+    }
+}
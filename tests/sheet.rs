@@ -0,0 +1,132 @@
+// Checks sprite sheets are scaled with each cell isolated from its neighbours.
+
+use scalefx_rs::{BorderMode, Image, ScaleFxOptions, SheetLayout, png_io, scale3x_sheet, scale9x_sheet, scale_sheet};
+
+const SPACER: u32 = 0xff00ffff; // The colour between cells.
+
+// Lays the images out in a grid of cells, filling the margin and spacing with the spacer colour
+// and any unused space in each cell with transparency.
+fn make_sheet(images: &[Image], columns: usize, layout: &SheetLayout) -> Image {
+    let rows = images.len().div_ceil(columns);
+    let width = layout.margin + columns * layout.cell_width + (columns - 1) * layout.spacing;
+    let height = layout.margin + rows * layout.cell_height + (rows - 1) * layout.spacing;
+    let mut pixels = vec![SPACER; width * height];
+    for (i, image) in images.iter().enumerate() {
+        let left = layout.margin + (i % columns) * (layout.cell_width + layout.spacing);
+        let top = layout.margin + (i / columns) * (layout.cell_height + layout.spacing);
+        for y in 0..layout.cell_height {
+            for x in 0..layout.cell_width {
+                let inside = x < image.width && y < image.height;
+                pixels[(top + y) * width + left + x] = if inside { image.pixels[y * image.width + x] } else { 0 };
+            }
+        }
+    }
+    Image::new(width, height, pixels)
+}
+
+fn crop(image: &Image, x: usize, y: usize, width: usize, height: usize) -> Image {
+    let pixels = image.pixels.chunks_exact(image.width).skip(y).take(height)
+        .flat_map(|row| &row[x..x + width])
+        .copied()
+        .collect();
+    Image::new(width, height, pixels)
+}
+
+fn sprites() -> Vec<Image> {
+    ["Keen", "Bip", "Mine", "Slug", "Worm"].iter().map(|name| png_io::load_png(&format!("readme/{}.png", name)).unwrap()).collect()
+}
+
+// Makes a cell-sized image from the sprite, as it appears in the sheet.
+fn as_cell(image: &Image, layout: &SheetLayout) -> Image {
+    make_sheet(std::slice::from_ref(image), 1, &SheetLayout { margin: 0, ..*layout })
+}
+
+#[test]
+fn cells_scale_as_if_alone() {
+    let sprites = sprites();
+    let width = sprites.iter().map(|sprite| sprite.width).max().unwrap();
+    let height = sprites.iter().map(|sprite| sprite.height).max().unwrap();
+    let layout = SheetLayout::new(width, height).with_spacing(2).with_margin(3);
+    let sheet = make_sheet(&sprites, 3, &layout);
+    for mode in [BorderMode::Transparent, BorderMode::Clamp] {
+        let options = ScaleFxOptions::default().with_border_mode(mode);
+        let scaled = scale9x_sheet(&sheet, &layout, &options).unwrap();
+        assert_eq!((scaled.width, scaled.height), (sheet.width * 9, sheet.height * 9));
+        let big = layout.scaled(9).unwrap();
+        for (i, sprite) in sprites.iter().enumerate() {
+            let left = big.margin + (i % 3) * (big.cell_width + big.spacing);
+            let top = big.margin + (i / 3) * (big.cell_height + big.spacing);
+            let expected = as_cell(sprite, &layout).scale9x(&options).unwrap();
+            assert_eq!(crop(&scaled, left, top, big.cell_width, big.cell_height), expected, "Cell {} with {:?}", i, mode);
+        }
+        // The margin and spacing are simply enlarged.
+        assert!(scaled.pixels[..big.margin * scaled.width].iter().all(|&pixel| pixel == SPACER));
+        let spacing_column = big.margin + big.cell_width;
+        assert!((0..scaled.height).all(|y| scaled.pixels[y * scaled.width + spacing_column] == SPACER));
+    }
+}
+
+#[test]
+fn touching_cells_do_not_bleed() {
+    // Two solid cells with no spacing: scaled as one image, the boundary between them would be smoothed.
+    let layout = SheetLayout::new(4, 4);
+    let sheet = make_sheet(&[Image::new(4, 4, vec![0xff0000ff; 16]), Image::new(4, 4, vec![0x0000ffff; 16])], 2, &layout);
+    let options = ScaleFxOptions::default().with_border_mode(BorderMode::Clamp);
+    let scaled = scale3x_sheet(&sheet, &layout, &options).unwrap();
+    for (x, &pixel) in scaled.pixels.iter().enumerate().take(scaled.width) {
+        assert_eq!(pixel, if x < 12 { 0xff0000ff } else { 0x0000ffff });
+    }
+}
+
+#[test]
+fn scale9x_matches_scale3x_twice() {
+    let layout = SheetLayout::new(24, 32).with_spacing(1);
+    let sheet = make_sheet(&sprites(), 2, &layout);
+    let options = ScaleFxOptions::default();
+    let once = scale9x_sheet(&sheet, &layout, &options).unwrap();
+    let twice = scale3x_sheet(&scale3x_sheet(&sheet, &layout, &options).unwrap(), &layout.scaled(3).unwrap(), &options).unwrap();
+    assert_eq!(once, twice);
+}
+
+#[test]
+fn threads_match_serial() {
+    let layout = SheetLayout::new(24, 32).with_margin(1);
+    let sheet = make_sheet(&sprites(), 2, &layout);
+    let serial = scale3x_sheet(&sheet, &layout, &ScaleFxOptions::default()).unwrap();
+    let threaded = scale3x_sheet(&sheet, &layout, &ScaleFxOptions::default().with_threads(3)).unwrap();
+    assert_eq!(serial, threaded);
+}
+
+#[test]
+fn partial_cells_are_enlarged() {
+    // The sheet is narrower than two whole cells, so the leftover pixels are just enlarged.
+    let image = png_io::load_png("readme/Keen.png").unwrap();
+    let layout = SheetLayout::new(image.width / 2 + 1, image.height);
+    let scaled = scale3x_sheet(&image, &layout, &ScaleFxOptions::default()).unwrap();
+    let leftover = image.width - layout.cell_width;
+    for y in 0..scaled.height {
+        for x in 0..leftover * 3 {
+            let source = image.pixels[(y / 3) * image.width + layout.cell_width + x / 3];
+            assert_eq!(scaled.pixels[y * scaled.width + layout.cell_width * 3 + x], source);
+        }
+    }
+}
+
+#[test]
+fn empty_cells_are_an_error() {
+    let image = Image::new(2, 2, vec![0; 4]);
+    assert!(scale3x_sheet(&image, &SheetLayout::new(0, 2), &ScaleFxOptions::default()).is_err());
+}
+
+#[test]
+fn overflowing_layouts_are_an_error() {
+    let image = Image::new(2, 2, vec![0; 4]);
+    let options = ScaleFxOptions::default();
+    assert!(scale3x_sheet(&image, &SheetLayout::new(1, 1).with_spacing(usize::MAX), &options).is_err());
+    assert!(scale3x_sheet(&image, &SheetLayout::new(1, 1).with_margin(usize::MAX), &options).is_err());
+    assert!(SheetLayout::new(1, 1).with_margin(usize::MAX / 2).scaled(3).is_err());
+    assert!(scale_sheet(&image, &SheetLayout::new(1, 1).with_spacing(usize::MAX / 2), 9, &options).is_err());
+    // Big but not overflowing, they just leave fewer whole cells.
+    assert!(scale3x_sheet(&image, &SheetLayout::new(1, 1).with_spacing(usize::MAX - 1), &options).is_ok());
+    assert!(scale3x_sheet(&image, &SheetLayout::new(1, 1).with_margin(usize::MAX - 1), &options).is_ok());
+}
@@ -0,0 +1,66 @@
+// Checks the SIMD passes produce exactly the same output as the scalar ones.
+
+mod common;
+
+use common::sprites;
+use scalefx_rs::{Image, ScaleFxOptions};
+
+// Random colours from a small palette, with some transparent and semi-transparent pixels, to exercise every branch of the distance maths.
+fn noise(width: usize, height: usize, seed: u32) -> Image {
+    let palette = [0x000000ff, 0xffffffff, 0xff000000, 0x12345680, 0x1234567f, 0x80c040ff, 0x7fc041ff, 0xfe01a0ff];
+    let mut state = seed;
+    let pixels = (0..width * height).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        palette[state as usize % palette.len()]
+    }).collect();
+    Image::new(width, height, pixels)
+}
+
+// The default options without the anti-aliasing filter, which the SIMD passes branch on.
+fn unfiltered() -> ScaleFxOptions {
+    let mut options = ScaleFxOptions::default();
+    options.is_filter_aa_enabled = false;
+    options
+}
+
+fn assert_simd_matches_scalar(name: &str, image: &Image, options: &ScaleFxOptions) {
+    let simd = image.scale3x(&options.with_simd(true)).unwrap();
+    let scalar = image.scale3x(&options.with_simd(false)).unwrap();
+    let differences = simd.pixels.iter().zip(&scalar.pixels).filter(|(a, b)| a != b).count();
+    assert_eq!(differences, 0, "{} differs in {} pixels with {:?}", name, differences, options);
+}
+
+#[test]
+fn simd_matches_scalar_on_sample_sprites() {
+    let options = ScaleFxOptions::default();
+    for (name, image) in sprites() {
+        assert_simd_matches_scalar(&name, &image, &options);
+    }
+}
+
+#[test]
+fn simd_matches_scalar_with_other_options() {
+    let sprites = sprites();
+    let options = [
+        unfiltered(),
+        ScaleFxOptions::default().with_threshold(ScaleFxOptions::MIN_THRESHOLD),
+        ScaleFxOptions::default().with_threshold(0.23),
+        ScaleFxOptions::default().with_threshold(ScaleFxOptions::MAX_THRESHOLD),
+    ];
+    for options in &options {
+        for (name, image) in sprites.iter().step_by(5) {
+            assert_simd_matches_scalar(name, image, options);
+        }
+    }
+}
+
+#[test]
+fn simd_matches_scalar_on_noise() {
+    for seed in 1..10 {
+        let image = noise(37, 23, seed);
+        assert_simd_matches_scalar("noise", &image, &ScaleFxOptions::default());
+        assert_simd_matches_scalar("noise", &image, &unfiltered());
+    }
+}
@@ -0,0 +1,69 @@
+// Checks tag maps reproduce the scale they were made from, on any image of the same size.
+
+mod common;
+
+use common::{load, recolour, rotate_channels, temp_path};
+use scalefx_rs::{BorderMode, ScaleFxError, ScaleFxOptions, TagMap};
+
+#[test]
+fn own_tags_match_scaling() {
+    let image = load("BloogletR");
+    let hybrid = ScaleFxOptions::default().with_hybrid(true).with_border_mode(BorderMode::Clamp);
+    for options in [ScaleFxOptions::default(), hybrid] {
+        for factor in [1, 2, 3, 4, 9] {
+            let tags = TagMap::new(&image, factor, &options).unwrap();
+            assert_eq!(Some(tags.native_factor()), scalefx_rs::factor::native_factor(factor));
+            assert_eq!(tags.apply(&image, factor, &options).unwrap(), image.scale(factor, &options).unwrap(), "{}x", factor);
+        }
+    }
+}
+
+#[test]
+fn palette_variants_get_the_same_shape() {
+    let original = load("BloogletR");
+    let variant = recolour(&original, rotate_channels);
+    let options = ScaleFxOptions::default();
+    let tags = TagMap::new(&original, 9, &options).unwrap();
+    let expected = recolour(&original.scale9x(&options).unwrap(), rotate_channels);
+    assert_ne!(variant.scale9x(&options).unwrap(), expected, "Scaled on its own, the variant's different distances change its shape");
+    assert_eq!(tags.apply(&variant, 9, &options).unwrap(), expected);
+}
+
+#[test]
+fn bytes_round_trip() {
+    let tags = TagMap::new(&load("Bip"), 9, &ScaleFxOptions::default()).unwrap();
+    let bytes = tags.to_bytes().unwrap();
+    assert!(bytes.starts_with(b"SFXTAGS1"));
+    assert_eq!(bytes.len(), 8 + 3 * 4 + (tags.width * tags.height * (1 + 9)) * 4);
+    assert_eq!(TagMap::from_bytes(&bytes).unwrap(), tags);
+
+    let path = temp_path("bytes_round_trip.tags");
+    tags.save(&path).unwrap();
+    let loaded = TagMap::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.unwrap(), tags);
+}
+
+#[test]
+fn invalid_files_are_an_error() {
+    let bytes = TagMap::new(&load("Bip"), 3, &ScaleFxOptions::default()).unwrap().to_bytes().unwrap();
+    let is_decode_error = |bytes: &[u8]| matches!(TagMap::from_bytes(bytes), Err(ScaleFxError::Decode(_)));
+    assert!(is_decode_error(b"NOTTAGS1"), "Wrong magic");
+    assert!(is_decode_error(&bytes[..14]), "Header cut short");
+    assert!(is_decode_error(&bytes[..bytes.len() - 1]), "Tags cut short");
+    assert!(is_decode_error(&[bytes.as_slice(), &[0; 4]].concat()), "Data after the tags");
+    let mut out_of_range = bytes.clone();
+    out_of_range[20] = 0x09; // The first tag of the first pixel.
+    assert!(is_decode_error(&out_of_range), "Tag out of range");
+}
+
+#[test]
+fn mismatched_images_and_factors_are_an_error() {
+    let options = ScaleFxOptions::default();
+    let tags = TagMap::new(&load("BloogletR"), 9, &options).unwrap();
+    let is_mismatch = |result| matches!(result, Err(ScaleFxError::TagMapMismatch(_)));
+    assert!(is_mismatch(tags.apply(&load("BloogletG"), 9, &options)), "A different size");
+    assert!(is_mismatch(tags.apply(&load("BloogletR"), 3, &options)), "A different native factor");
+    assert!(tags.apply(&load("BloogletR"), 0, &options).is_err());
+    assert!(tags.apply(&load("BloogletR"), 5, &options).is_ok(), "Shrunk from the same native factor");
+}
@@ -0,0 +1,33 @@
+// Checks splitting the passes across threads gives exactly the same output as running them serially.
+
+mod common;
+
+use common::load;
+use scalefx_rs::{Image, ScaleFxOptions};
+
+#[test]
+fn threads_match_serial() {
+    let serial = ScaleFxOptions::default().with_threads(1);
+    for name in ["Keen", "Dopefish", "sq3.fixed"] {
+        let image = load(name);
+        let expected = image.scale3x(&serial).unwrap();
+        for threads in [2, 3, 7, 0] {
+            assert_eq!(image.scale3x(&serial.with_threads(threads)).unwrap(), expected, "{} on {} threads", name, threads);
+        }
+    }
+}
+
+#[test]
+fn threads_match_serial_at_9x() {
+    // The second 3x step runs on the first's output, which has more rows to split up.
+    let serial = ScaleFxOptions::default().with_threads(1);
+    let image = load("Bip");
+    assert_eq!(image.scale9x(&serial.with_threads(4)).unwrap(), image.scale9x(&serial).unwrap());
+}
+
+#[test]
+fn more_threads_than_rows() {
+    let image = Image::new(3, 2, vec![0xff0000ff, 0, 0x0000ffff, 0, 0x00ff00ff, 0]);
+    let serial = ScaleFxOptions::default().with_threads(1);
+    assert_eq!(image.scale3x(&serial.with_threads(16)).unwrap(), image.scale3x(&serial).unwrap());
+}
@@ -0,0 +1,80 @@
+// Checks that wrapping the borders makes tileable inputs scale to tileable outputs.
+
+use scalefx_rs::{BorderMode, Image, ScaleFxOptions, png_io};
+
+// Repeats an image across and down.
+fn tile(image: &Image, across: usize, down: usize) -> Image {
+    let mut pixels = Vec::with_capacity(image.pixels.len() * across * down);
+    for _ in 0..down {
+        for row in image.pixels.chunks_exact(image.width) {
+            for _ in 0..across { pixels.extend_from_slice(row) }
+        }
+    }
+    Image::new(image.width * across, image.height * down, pixels)
+}
+
+// Cuts out part of an image.
+fn crop(image: &Image, x: usize, y: usize, width: usize, height: usize) -> Image {
+    let pixels = image.pixels.chunks_exact(image.width).skip(y).take(height)
+        .flat_map(|row| &row[x..x + width])
+        .copied()
+        .collect();
+    Image::new(width, height, pixels)
+}
+
+// A brick wall whose mortar lines and diagonal cracks run off every edge.
+fn bricks() -> Image {
+    let (width, height) = (16, 12);
+    let pixels = (0..width * height).map(|i| {
+        let (x, y) = (i % width, i / width);
+        let offset = if (y / 3) % 2 == 0 { 0 } else { 4 };
+        if y % 3 == 2 || (x + offset) % 8 == 7 { 0x808080ff }
+        else if (x + y) % 16 == 0 { 0x402010ff }
+        else { 0xa04020ff }
+    }).collect();
+    Image::new(width, height, pixels)
+}
+
+// Scales the image surrounded by copies of itself in the default transparent mode, so its edges are
+// calculated from real neighbours rather than the border, and returns the middle copy.
+// There are enough copies that the border is at least 8 pixels away, which is as far as ScaleFX looks.
+fn scale_among_copies(image: &Image) -> Image {
+    let across = 8usize.div_ceil(image.width) * 2 + 1;
+    let down = 8usize.div_ceil(image.height) * 2 + 1;
+    let big = tile(image, across, down).scale3x(&ScaleFxOptions::default()).unwrap();
+    crop(&big, across / 2 * image.width * 3, down / 2 * image.height * 3, image.width * 3, image.height * 3)
+}
+
+fn differing_pixels(a: &Image, b: &Image) -> usize {
+    a.pixels.iter().zip(&b.pixels).filter(|(a, b)| a != b).count()
+}
+
+#[test]
+fn wrapped_edges_continue_across_the_seams() {
+    let wrap = ScaleFxOptions::default().with_border_mode(BorderMode::Wrap);
+    let tiny = Image::new(3, 2, vec![0x000000ff, 0xffffffff, 0xffffffff, 0xffffffff, 0x000000ff, 0xffffffff]);
+    for image in [bricks(), tiny, png_io::load_png("readme/Keen.png").unwrap()] {
+        let scaled = image.scale3x(&wrap).unwrap();
+        // The left/right and top/bottom edges of the middle copy border its neighbouring copies,
+        // so every pixel matching means the output continues across both seams.
+        let expected = scale_among_copies(&image);
+        assert_eq!(differing_pixels(&scaled, &expected), 0);
+    }
+}
+
+#[test]
+fn wrapped_output_tiles_seamlessly() {
+    let wrap = ScaleFxOptions::default().with_border_mode(BorderMode::Wrap);
+    let image = bricks();
+    let tiled_then_scaled = tile(&image, 2, 2).scale3x(&wrap).unwrap();
+    let scaled_then_tiled = tile(&image.scale3x(&wrap).unwrap(), 2, 2);
+    assert_eq!(tiled_then_scaled, scaled_then_tiled);
+}
+
+#[test]
+fn transparent_edges_do_not_tile() {
+    // Without wrapping, the edges are smoothed into the transparent border instead.
+    let image = bricks();
+    let scaled = image.scale3x(&ScaleFxOptions::default()).unwrap();
+    assert!(differing_pixels(&scaled, &scale_among_copies(&image)) > 0);
+}